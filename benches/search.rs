@@ -0,0 +1,73 @@
+//! Compares the e-matching machine against the recursive pattern interpreter
+//! on the `Rule::rules()` set. Run with `cargo bench`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use egraphs::graphs::*;
+use egraphs::rules::*;
+use egraphs::runner::*;
+
+const ITERATIONS: usize = 6;
+const REPETITIONS: usize = 20;
+
+fn build_graph(rules: &[Rule]) -> EGraph {
+    let mut graph = EGraph::init();
+    graph.add_expression(Expression::divide(
+        Expression::multiply(Expression::variable("x"), Expression::constant(-6)),
+        Expression::multiply(Expression::variable("x"), Expression::constant(-3)),
+    ));
+    graph.add_expression(Expression::add(
+        Expression::multiply(
+            Expression::variable("y"),
+            Expression::add(Expression::variable("x"), Expression::constant(1)),
+        ),
+        Expression::subtract(
+            Expression::variable("z"),
+            Expression::negate(Expression::variable("y")),
+        ),
+    ));
+
+    let mut runner = Runner::new(
+        graph,
+        Limits {
+            iterations: ITERATIONS,
+            ..Limits::default()
+        },
+    );
+    runner.run(rules).unwrap();
+    runner.egraph
+}
+
+fn time(mut f: impl FnMut() -> usize) -> (Duration, usize) {
+    let start = Instant::now();
+    let mut matches = 0;
+    for _ in 0..REPETITIONS {
+        matches = black_box(f());
+    }
+    (start.elapsed() / REPETITIONS as u32, matches)
+}
+
+fn main() {
+    let rules = Rule::rules();
+    let graph = build_graph(&rules);
+    println!("graph with {} classes", graph.classes().count());
+
+    let (recursive, recursive_matches) = time(|| {
+        rules
+            .iter()
+            .map(|rule| graph.search_recursive(&rule.lhs).len())
+            .sum()
+    });
+    let (machine, machine_matches) =
+        time(|| rules.iter().map(|rule| rule.search(&graph).len()).sum());
+    assert_eq!(recursive_matches, machine_matches);
+
+    println!("matches per search: {}", machine_matches);
+    println!("recursive interpreter: {:?}", recursive);
+    println!("e-matching machine:    {:?}", machine);
+    println!(
+        "speedup: {:.2}x",
+        recursive.as_secs_f64() / machine.as_secs_f64()
+    );
+}
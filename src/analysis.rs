@@ -0,0 +1,104 @@
+use std::fmt;
+
+use crate::explain::Justification;
+use crate::graphs::*;
+
+/// Data attached to every e-class and kept up to date as the graph changes.
+///
+/// A new class gets the data `make` computes from its first node. When two
+/// classes are merged their data is combined with `merge`, and whenever the
+/// data of a class changes its parents are recomputed during `rebuild`. After
+/// each of these steps `modify` may change the class based on its data, for
+/// instance by adding nodes to it.
+pub trait Analysis<L: Language>: Sized {
+    type Data: fmt::Debug + Clone + PartialEq;
+
+    /// Computes the data of a node from the data of its children.
+    fn make(egraph: &EGraph<L, Self>, node: &Node<L>) -> Self::Data;
+
+    /// Combines the data of two merged classes into `a`.
+    fn merge(&mut self, a: &mut Self::Data, b: Self::Data);
+
+    /// Changes the class `id` based on its data. Does nothing by default.
+    fn modify(_egraph: &mut EGraph<L, Self>, _id: Id) {}
+}
+
+/// The analysis that does not compute anything.
+impl<L: Language> Analysis<L> for () {
+    type Data = ();
+
+    fn make(_egraph: &EGraph<L, Self>, _node: &Node<L>) -> Self::Data {}
+
+    fn merge(&mut self, _a: &mut Self::Data, _b: Self::Data) {}
+}
+
+/// Tracks the constant value of every class whose value is known, and adds a
+/// `Const` node to such classes.
+#[derive(Debug, Clone, Default)]
+pub struct ConstantFolding;
+
+impl Analysis<NodeType> for ConstantFolding {
+    type Data = Option<i64>;
+
+    fn make(egraph: &EGraph<NodeType, Self>, node: &Node<NodeType>) -> Self::Data {
+        let children: Option<Vec<Expression>> = node
+            .children
+            .iter()
+            .map(|child| Some(Expression::constant(egraph.get_class(*child).ok()?.data?)))
+            .collect();
+
+        Expression {
+            t: node.t.clone(),
+            children: children?,
+        }
+        .const_eval()
+    }
+
+    /// Keeps the known value. Two different values can only meet if a rule is
+    /// unsound, in which case the value of `a` wins.
+    fn merge(&mut self, a: &mut Self::Data, b: Self::Data) {
+        if a.is_none() {
+            *a = b;
+        }
+    }
+
+    /// Adds the value of the class as a constant. The constant is derived
+    /// from a node of the class whose children are constants, so that an
+    /// explanation can show the evaluation as a single step.
+    fn modify(egraph: &mut EGraph<NodeType, Self>, id: Id) {
+        let Some(class) = egraph.get_class(id).ok() else {
+            return;
+        };
+        let Some(value) = class.data else {
+            return;
+        };
+        let folded = class.children.iter().find_map(|node| {
+            let children: Option<Vec<Expression>> = node
+                .children
+                .iter()
+                .map(|child| Some(Expression::constant(egraph.get_class(*child).ok()?.data?)))
+                .collect();
+            let expression = Expression {
+                t: node.t.clone(),
+                children: children?,
+            };
+            (!expression.children.is_empty() && expression.const_eval() == Some(value))
+                .then_some(expression)
+        });
+
+        // Otherwise the value came from merging with a class that already
+        // holds the constant, and there is nothing to fold.
+        let Some(folded) = folded else {
+            return;
+        };
+
+        let const_id = egraph.add_expression(Expression::constant(value));
+        let folded_id = egraph.add_expression(folded);
+        if egraph.find(folded_id) == egraph.find(const_id) {
+            return;
+        }
+        egraph
+            .union_because(folded_id, const_id, Justification::ConstantFold)
+            .expect("all ids come from this graph");
+    }
+}
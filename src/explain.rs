@@ -0,0 +1,545 @@
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt;
+
+use crate::graphs::*;
+
+/// Why two classes were merged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Justification {
+    /// A rule rewrote the first term into the second. The substitution is the
+    /// one of the match, with variables rebound to the nodes the rule relied
+    /// on, see `Rule::rebind`.
+    Rule { name: String, subst: Subst },
+    /// Both nodes have the same operator and equivalent children.
+    Congruence,
+    /// The first term is an operator applied to constants and evaluates to
+    /// the second.
+    ConstantFold,
+    /// `EGraph::union` was called directly.
+    Asserted,
+}
+
+impl fmt::Display for Justification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Justification::Rule { name, .. } => write!(f, "{}", name),
+            Justification::Congruence => write!(f, "congruence"),
+            Justification::ConstantFold => write!(f, "constant folding"),
+            Justification::Asserted => write!(f, "assumption"),
+        }
+    }
+}
+
+/// A single rewrite of a subterm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofStep<L = NodeType> {
+    /// Why the subterm could be rewritten. Never `Congruence`, since those
+    /// steps are broken down into steps on the children.
+    pub justification: Justification,
+    /// Whether the justification was used from right to left, for instance a
+    /// rule rewriting its right-hand side into its left-hand side.
+    pub backward: bool,
+    /// The child indices leading from the root to the rewritten subterm.
+    pub position: Vec<usize>,
+    /// The whole term after the step.
+    pub term: Expression<L>,
+}
+
+/// A sequence of rewrite steps turning `start` into the term of the last
+/// step, see `EGraph::explain_equivalence`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation<L = NodeType> {
+    pub start: Expression<L>,
+    pub steps: Vec<ProofStep<L>>,
+}
+
+impl<L: Language> Explanation<L> {
+    /// The term the explanation ends with.
+    pub fn end(&self) -> &Expression<L> {
+        self.steps.last().map_or(&self.start, |step| &step.term)
+    }
+
+    /// The number of steps.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Shortens the explanation without changing where it starts and ends,
+    /// until neither of these applies anymore:
+    ///
+    /// - If a term appears twice, the steps between the two are dropped.
+    /// - If a subterm is rewritten and later rewritten back at the same
+    ///   position, with only steps elsewhere in the term in between, both
+    ///   rewrites are dropped. This removes round trips such as applying
+    ///   commutativity twice.
+    pub fn simplify(&self) -> Explanation<L> {
+        let mut explanation = self.clone();
+        while explanation.remove_cycle() || explanation.remove_round_trip() {}
+        explanation
+    }
+
+    /// The term before the step with the given index.
+    fn before(&self, index: usize) -> &Expression<L> {
+        match index {
+            0 => &self.start,
+            _ => &self.steps[index - 1].term,
+        }
+    }
+
+    fn remove_cycle(&mut self) -> bool {
+        let mut last: HashMap<&Expression<L>, usize> = HashMap::new();
+        for index in 0..=self.steps.len() {
+            last.insert(self.before(index), index);
+        }
+
+        for index in 0..self.steps.len() {
+            let repeated = last[self.before(index)];
+            if repeated > index {
+                self.steps.drain(index..repeated);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn remove_round_trip(&mut self) -> bool {
+        for i in 0..self.steps.len() {
+            let position = &self.steps[i].position;
+            let original = self
+                .before(i)
+                .subterm(position)
+                .expect("steps rewrite existing positions");
+
+            for j in i + 1..self.steps.len() {
+                let other = &self.steps[j].position;
+                if other == position {
+                    if self.steps[j].term.subterm(position) != Some(original) {
+                        break;
+                    }
+
+                    // The steps in between did not touch the subterm, so
+                    // they can work on the original one instead.
+                    let original = original.clone();
+                    let position = position.clone();
+                    for step in self.steps[i + 1..j].iter_mut() {
+                        *step
+                            .term
+                            .subterm_mut(&position)
+                            .expect("steps elsewhere keep the position") = original.clone();
+                    }
+                    self.steps.remove(j);
+                    self.steps.remove(i);
+                    return true;
+                }
+                if other.starts_with(position) || position.starts_with(other) {
+                    break;
+                }
+            }
+        }
+        false
+    }
+}
+
+impl<L: Language> fmt::Display for Explanation<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.start)?;
+        for step in self.steps.iter() {
+            write!(f, "\n= {}    by {}", step.term, step.justification)?;
+            if step.backward {
+                write!(f, " (backwards)")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An explanation shortened by `EGraph::explain_minimized`, together with the
+/// length of the explanation it replaces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinimizedExplanation<L = NodeType> {
+    pub explanation: Explanation<L>,
+    /// The number of steps of the explanation `explain_equivalence` gives.
+    pub original_length: usize,
+}
+
+impl<L: Language> fmt::Display for MinimizedExplanation<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}\n({} steps, {} before minimization)",
+            self.explanation,
+            self.explanation.len(),
+            self.original_length
+        )
+    }
+}
+
+/// A union between the terms of two ids, where the justification turns the
+/// term of `a` into the term of `b`.
+#[derive(Debug, Clone)]
+struct Edge {
+    a: Id,
+    b: Id,
+    justification: Justification,
+    /// Whether the union merged two classes, making the edge part of the
+    /// forest.
+    merged: bool,
+}
+
+/// Whether a justification turns the first term into the second.
+pub(crate) type Holds<'a, L> = &'a dyn Fn(&Justification, &Expression<L>, &Expression<L>) -> bool;
+
+/// The edges older than a bound that a shortest path may use.
+struct Usable<'a> {
+    bound: usize,
+    edge: &'a dyn Fn(usize) -> bool,
+}
+
+/// What `Explain::union` changed, so that it can be undone.
+#[derive(Debug, Clone)]
+pub(crate) struct ExplainUnion {
+    a: Id,
+    b: Id,
+    /// Whether an edge was added, or the ids already had one.
+    added: bool,
+    /// The previous parents of the ids whose parent changed.
+    parents: Vec<(Id, Option<(Id, usize)>)>,
+}
+
+/// Records the justification of every union. Every id stands for the node it
+/// was created for, with its children as they were passed to `EGraph::add`,
+/// so every id represents exactly one term.
+///
+/// Unions that merged two classes form a forest, and two terms are explained
+/// by the path between them. Unions of terms that were already equivalent
+/// are kept as well, since they may give shorter explanations.
+#[derive(Debug, Clone)]
+pub(crate) struct Explain<L> {
+    nodes: Vec<Node<L>>,
+    /// The id of every node before canonicalization.
+    memo: HashMap<Node<L>, Id>,
+    /// All unions, in the order they happened.
+    edges: Vec<Edge>,
+    /// The unions touching each id.
+    adjacent: Vec<Vec<usize>>,
+    /// The pairs of ids that already have an edge, smaller id first.
+    connected: HashSet<(Id, Id)>,
+    /// For every id its parent in the forest and the edge leading there, or
+    /// `None` for roots.
+    parents: Vec<Option<(Id, usize)>>,
+}
+
+impl<L: Language> Explain<L> {
+    pub(crate) fn new() -> Explain<L> {
+        Explain {
+            nodes: vec![],
+            memo: HashMap::new(),
+            edges: vec![],
+            adjacent: vec![],
+            connected: HashSet::new(),
+            parents: vec![],
+        }
+    }
+
+    /// The id created for exactly this node, if there is one.
+    pub(crate) fn lookup(&self, node: &Node<L>) -> Option<Id> {
+        self.memo.get(node).copied()
+    }
+
+    /// Records the node a new id was created for.
+    pub(crate) fn add(&mut self, node: Node<L>, id: Id) {
+        debug_assert_eq!(usize::from(id), self.nodes.len());
+        self.memo.insert(node.clone(), id);
+        self.nodes.push(node);
+        self.adjacent.push(vec![]);
+        self.parents.push(None);
+    }
+
+    /// Records that the term of `a` equals the term of `b`. If the two ids
+    /// are not connected yet, `merged` must be true.
+    pub(crate) fn union(
+        &mut self,
+        a: Id,
+        b: Id,
+        justification: Justification,
+        merged: bool,
+    ) -> ExplainUnion {
+        let mut undo = ExplainUnion {
+            a,
+            b,
+            added: false,
+            parents: vec![],
+        };
+        if a == b || !self.connected.insert((a.min(b), a.max(b))) {
+            debug_assert!(!merged, "merged ids must not be connected yet");
+            return undo;
+        }
+        undo.added = true;
+
+        let edge = self.edges.len();
+        self.edges.push(Edge {
+            a,
+            b,
+            justification,
+            merged,
+        });
+        self.adjacent[usize::from(a)].push(edge);
+        self.adjacent[usize::from(b)].push(edge);
+
+        if merged {
+            undo.parents = self.reroot(a);
+            self.parents[usize::from(a)] = Some((b, edge));
+        }
+        undo
+    }
+
+    /// Makes `id` the root of its tree by reversing the edges on the way,
+    /// returning the previous parents of the ids on the path.
+    fn reroot(&mut self, id: Id) -> Vec<(Id, Option<(Id, usize)>)> {
+        let mut path = vec![id];
+        while let Some((parent, _)) = self.parents[usize::from(*path.last().expect("not empty"))] {
+            path.push(parent);
+        }
+        let previous = path
+            .iter()
+            .map(|id| (*id, self.parents[usize::from(*id)]))
+            .collect();
+
+        for pair in path.windows(2).rev() {
+            let (_, edge) = self.parents[usize::from(pair[0])].expect("only the last id is a root");
+            self.parents[usize::from(pair[1])] = Some((pair[0], edge));
+        }
+        self.parents[usize::from(id)] = None;
+        previous
+    }
+
+    /// Undoes the most recent `add`.
+    pub(crate) fn undo_add(&mut self) {
+        let node = self.nodes.pop().expect("an id was added");
+        self.memo.remove(&node);
+        self.adjacent.pop();
+        self.parents.pop();
+    }
+
+    /// Undoes the most recent `union`.
+    pub(crate) fn undo_union(&mut self, undo: ExplainUnion) {
+        if !undo.added {
+            return;
+        }
+        for (id, parent) in undo.parents {
+            self.parents[usize::from(id)] = parent;
+        }
+        self.edges.pop();
+        self.adjacent[usize::from(undo.a)].pop();
+        self.adjacent[usize::from(undo.b)].pop();
+        self.connected
+            .remove(&(undo.a.min(undo.b), undo.a.max(undo.b)));
+    }
+
+    /// The term the id was created for.
+    pub(crate) fn term(&self, id: Id) -> Expression<L> {
+        let node = &self.nodes[usize::from(id)];
+        Expression {
+            t: node.t.clone(),
+            children: node
+                .children
+                .iter()
+                .map(|child| self.term(*child))
+                .collect(),
+        }
+    }
+
+    /// Explains why the terms of two connected ids are equal. The explanation
+    /// follows the forest, or if `holds` is given, greedily picks the
+    /// cheapest path among the unions of the forest, congruences, and the
+    /// unions whose justification `holds` accepts for the terms of their ids.
+    pub(crate) fn explain(&self, a: Id, b: Id, holds: Option<Holds<L>>) -> Explanation<L> {
+        let start = self.term(a);
+        let mut current = start.clone();
+        let mut steps = vec![];
+        match holds {
+            Some(holds) => {
+                let checked = RefCell::new(HashMap::new());
+                let edge = |edge: usize| {
+                    *checked
+                        .borrow_mut()
+                        .entry(edge)
+                        .or_insert_with(|| self.is_usable(edge, holds))
+                };
+                let usable = Usable {
+                    bound: self.edges.len(),
+                    edge: &edge,
+                };
+                self.explain_path(a, b, Some(&usable), &mut vec![], &mut current, &mut steps);
+            }
+            None => self.explain_path(a, b, None, &mut vec![], &mut current, &mut steps),
+        }
+        Explanation { start, steps }
+    }
+
+    /// Whether a shortest path may use an edge. Edges of the forest are
+    /// always usable, so that a path exists, and so are congruences, whose
+    /// children are explained separately.
+    fn is_usable(&self, edge: usize, holds: Holds<L>) -> bool {
+        let Edge {
+            a,
+            b,
+            justification,
+            merged,
+        } = &self.edges[edge];
+        *merged
+            || *justification == Justification::Congruence
+            || holds(justification, &self.term(*a), &self.term(*b))
+    }
+
+    /// Adds the steps rewriting the subterm of `current` at `position`, which
+    /// is the term of `a`, into the term of `b`. With `usable` edges, the
+    /// cheapest path among them is taken instead of the path in the forest.
+    fn explain_path(
+        &self,
+        a: Id,
+        b: Id,
+        usable: Option<&Usable>,
+        position: &mut Vec<usize>,
+        current: &mut Expression<L>,
+        steps: &mut Vec<ProofStep<L>>,
+    ) {
+        let path = match usable {
+            Some(usable) => self.shortest_path(a, b, usable),
+            None => self.forest_path(a, b),
+        };
+
+        for (from, edge) in path {
+            let edge_data = &self.edges[edge];
+            let (to, forward) = if edge_data.a == from {
+                (edge_data.b, true)
+            } else {
+                (edge_data.a, false)
+            };
+
+            if edge_data.justification == Justification::Congruence {
+                // The children were equal when the congruence was found, so
+                // their explanations only use older edges and this terminates.
+                let from_children = &self.nodes[usize::from(from)].children;
+                let to_children = &self.nodes[usize::from(to)].children;
+                for (i, (x, y)) in from_children.iter().zip(to_children).enumerate() {
+                    position.push(i);
+                    let older = usable.map(|usable| Usable {
+                        bound: edge,
+                        edge: usable.edge,
+                    });
+                    self.explain_path(*x, *y, older.as_ref(), position, current, steps);
+                    position.pop();
+                }
+                continue;
+            }
+
+            *current
+                .subterm_mut(position)
+                .expect("the position exists in the current term") = self.term(to);
+            steps.push(ProofStep {
+                justification: edge_data.justification.clone(),
+                backward: !forward,
+                position: position.clone(),
+                term: current.clone(),
+            });
+        }
+    }
+
+    /// The edges on the path from `a` to `b` in the forest, each with the id
+    /// it is left from.
+    fn forest_path(&self, a: Id, b: Id) -> Vec<(Id, usize)> {
+        let mut ancestors = vec![a];
+        while let Some((parent, _)) =
+            self.parents[usize::from(*ancestors.last().expect("not empty"))]
+        {
+            ancestors.push(parent);
+        }
+        let depths: HashMap<Id, usize> = ancestors
+            .iter()
+            .enumerate()
+            .map(|(depth, id)| (*id, depth))
+            .collect();
+
+        let mut descendants = vec![];
+        let mut common = b;
+        while !depths.contains_key(&common) {
+            let (parent, edge) =
+                self.parents[usize::from(common)].expect("only equivalent ids are explained");
+            descendants.push((parent, edge));
+            common = parent;
+        }
+
+        let mut path = vec![];
+        for id in ancestors[..depths[&common]].iter() {
+            let (_, edge) = self.parents[usize::from(*id)].expect("only roots have no parent");
+            path.push((*id, edge));
+        }
+        path.extend(descendants.into_iter().rev());
+        path
+    }
+
+    /// The cheapest path from `a` to `b` using only usable edges. A
+    /// congruence costs one step for every pair of children that differ,
+    /// which underestimates the steps it expands to, so the result is a
+    /// greedy choice rather than the shortest explanation.
+    fn shortest_path(&self, a: Id, b: Id, usable: &Usable) -> Vec<(Id, usize)> {
+        let mut costs: HashMap<Id, usize> = HashMap::from([(a, 0)]);
+        let mut previous: HashMap<Id, (Id, usize)> = HashMap::new();
+        let mut queue = BinaryHeap::from([Reverse((0, a))]);
+
+        while let Some(Reverse((cost, id))) = queue.pop() {
+            if id == b {
+                break;
+            }
+            if cost > costs[&id] {
+                continue;
+            }
+
+            for &edge in self.adjacent[usize::from(id)].iter() {
+                if edge >= usable.bound || !(usable.edge)(edge) {
+                    continue;
+                }
+                let Edge {
+                    a: x,
+                    b: y,
+                    justification,
+                    ..
+                } = &self.edges[edge];
+                let next = if *x == id { *y } else { *x };
+                let step_cost = match justification {
+                    Justification::Congruence => self.nodes[usize::from(*x)]
+                        .children
+                        .iter()
+                        .zip(self.nodes[usize::from(*y)].children.iter())
+                        .filter(|(x, y)| x != y)
+                        .count(),
+                    _ => 1,
+                };
+
+                let next_cost = cost + step_cost;
+                if costs.get(&next).is_none_or(|known| next_cost < *known) {
+                    costs.insert(next, next_cost);
+                    previous.insert(next, (id, edge));
+                    queue.push(Reverse((next_cost, next)));
+                }
+            }
+        }
+
+        let mut path = vec![];
+        let mut id = b;
+        while id != a {
+            let (from, edge) = previous[&id];
+            path.push((from, edge));
+            id = from;
+        }
+        path.reverse();
+        path
+    }
+}
@@ -0,0 +1,494 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::analysis::Analysis;
+use crate::graphs::*;
+
+/// Assigns a cost to a node given the costs of the terms chosen for its
+/// children, in order.
+pub trait CostFunction<L: Language> {
+    type Cost: fmt::Debug + Clone + PartialOrd;
+
+    fn cost(&mut self, node: &Node<L>, child_costs: &[Self::Cost]) -> Self::Cost;
+}
+
+/// The number of nodes in a term.
+#[derive(Debug, Clone, Default)]
+pub struct AstSize;
+
+impl<L: Language> CostFunction<L> for AstSize {
+    type Cost = usize;
+
+    fn cost(&mut self, _node: &Node<L>, child_costs: &[usize]) -> usize {
+        child_costs
+            .iter()
+            .fold(1, |sum, cost| sum.saturating_add(*cost))
+    }
+}
+
+/// The number of nodes on the longest path from the root of a term to a leaf.
+#[derive(Debug, Clone, Default)]
+pub struct AstDepth;
+
+impl<L: Language> CostFunction<L> for AstDepth {
+    type Cost = usize;
+
+    fn cost(&mut self, _node: &Node<L>, child_costs: &[usize]) -> usize {
+        1 + child_costs.iter().copied().max().unwrap_or(0)
+    }
+}
+
+/// Finds the cheapest term of every class under a cost function.
+///
+/// The best node of each class is computed by iterating to a fixpoint: a node
+/// is only considered once all of its children have a cost, so nodes that can
+/// only be completed through a cycle are never chosen.
+pub struct Extractor<'a, L: Language, N: Analysis<L>, C: CostFunction<L>> {
+    egraph: &'a EGraph<L, N>,
+    cost_function: C,
+    best: HashMap<Id, (C::Cost, Node<L>)>,
+}
+
+impl<'a, L: Language, N: Analysis<L>, C: CostFunction<L>> Extractor<'a, L, N, C> {
+    pub fn new(egraph: &'a EGraph<L, N>, cost_function: C) -> Extractor<'a, L, N, C> {
+        let mut extractor = Extractor {
+            egraph,
+            cost_function,
+            best: HashMap::new(),
+        };
+        extractor.find_costs();
+        extractor
+    }
+
+    /// Returns the cheapest term of the class `id` together with its cost.
+    pub fn find_best(&self, id: Id) -> Result<(C::Cost, Expression<L>), EGraphError> {
+        let id = self.egraph.try_find(id)?;
+        match self.best.get(&id) {
+            Some((cost, _)) => Ok((cost.clone(), self.build_expression(id))),
+            None => Err(EGraphError::Unextractable(id)),
+        }
+    }
+
+    /// Returns the cost of the cheapest term of the class `id`, or `None` if
+    /// the class has no finite term.
+    pub fn find_best_cost(&self, id: Id) -> Option<C::Cost> {
+        let id = self.egraph.try_find(id).ok()?;
+        self.best.get(&id).map(|(cost, _)| cost.clone())
+    }
+
+    /// Returns the node chosen for the class `id`.
+    pub fn find_best_node(&self, id: Id) -> Option<&Node<L>> {
+        let id = self.egraph.try_find(id).ok()?;
+        self.best.get(&id).map(|(_, node)| node)
+    }
+
+    fn build_expression(&self, id: Id) -> Expression<L> {
+        let (_, node) = &self.best[&self.egraph.find(id)];
+        Expression {
+            t: node.t.clone(),
+            children: node
+                .children
+                .iter()
+                .map(|child| self.build_expression(*child))
+                .collect(),
+        }
+    }
+
+    fn find_costs(&mut self) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            for (id, class) in self.egraph.classes() {
+                for node in class.children.iter() {
+                    let Some(cost) = self.node_cost(node) else {
+                        continue;
+                    };
+
+                    let better = match self.best.get(&id) {
+                        Some((best_cost, _)) => cost.partial_cmp(best_cost) == Some(Ordering::Less),
+                        None => true,
+                    };
+                    if better {
+                        self.best.insert(id, (cost, node.clone()));
+                        changed = true;
+                    }
+                }
+            }
+        }
+    }
+
+    /// The cost of a node, if all of its children already have a cost.
+    fn node_cost(&mut self, node: &Node<L>) -> Option<C::Cost> {
+        let child_costs: Option<Vec<C::Cost>> = node
+            .children
+            .iter()
+            .map(|child| {
+                self.best
+                    .get(&self.egraph.find(*child))
+                    .map(|(cost, _)| cost.clone())
+            })
+            .collect();
+        Some(self.cost_function.cost(node, &child_costs?))
+    }
+}
+
+/// Assigns a cost to a single node, independent of its children. Used by the
+/// `DagExtractor`, where the cost of a term is the sum over its distinct
+/// nodes. Costs should be positive.
+pub trait NodeCost<L: Language> {
+    fn node_cost(&mut self, node: &Node<L>) -> usize;
+}
+
+impl<L: Language> NodeCost<L> for AstSize {
+    fn node_cost(&mut self, _node: &Node<L>) -> usize {
+        1
+    }
+}
+
+/// Sums node costs over the tree, used to seed the DAG search with the best
+/// tree.
+struct TreeCost<'c, C>(&'c mut C);
+
+impl<L: Language, C: NodeCost<L>> CostFunction<L> for TreeCost<'_, C> {
+    type Cost = usize;
+
+    fn cost(&mut self, node: &Node<L>, child_costs: &[usize]) -> usize {
+        child_costs
+            .iter()
+            .fold(self.0.node_cost(node), |sum, cost| {
+                sum.saturating_add(*cost)
+            })
+    }
+}
+
+/// The result of a `DagExtractor`.
+#[derive(Debug, Clone)]
+pub struct DagExtraction<L> {
+    pub cost: usize,
+    pub expression: SharedExpression<L>,
+    /// Whether the search finished, proving that no cheaper DAG exists. If the
+    /// search ran out of steps, the cheapest DAG found so far is returned.
+    pub optimal: bool,
+}
+
+/// Finds the term of a class whose DAG is cheapest, paying for every class
+/// only once no matter how often it is shared.
+///
+/// The best tree under the summed node costs is used as the first solution,
+/// after which a branch-and-bound search chooses one node per reachable class.
+/// Partial solutions are pruned once their cost plus the cheapest node of each
+/// class still to be chosen reaches the best solution. The search gives up
+/// after `max_steps` choices and falls back to the best solution found.
+pub struct DagExtractor<'a, L: Language, N: Analysis<L>> {
+    egraph: &'a EGraph<L, N>,
+    /// The nodes of every reachable class, cheapest first.
+    nodes: HashMap<Id, Vec<(usize, Node<L>)>>,
+    max_steps: usize,
+    steps: usize,
+    best: Option<(usize, HashMap<Id, Node<L>>)>,
+}
+
+impl<'a, L: Language, N: Analysis<L>> DagExtractor<'a, L, N> {
+    pub fn new(egraph: &'a EGraph<L, N>, max_steps: usize) -> DagExtractor<'a, L, N> {
+        DagExtractor {
+            egraph,
+            nodes: HashMap::new(),
+            max_steps,
+            steps: 0,
+            best: None,
+        }
+    }
+
+    pub fn extract(
+        &mut self,
+        id: Id,
+        mut cost_function: impl NodeCost<L>,
+    ) -> Result<DagExtraction<L>, EGraphError> {
+        let root = self.egraph.try_find(id)?;
+        self.steps = 0;
+        self.best = None;
+
+        let greedy = self.greedy(root, &mut cost_function)?;
+        let greedy_cost = greedy
+            .values()
+            .map(|node| cost_function.node_cost(node))
+            .sum();
+        self.best = Some((greedy_cost, greedy));
+        self.collect_nodes(root, &mut cost_function);
+
+        let mut chosen = HashMap::new();
+        self.branch(vec![root], &mut chosen, 0);
+
+        let (cost, choice) = self
+            .best
+            .take()
+            .expect("the greedy solution is always present");
+        Ok(DagExtraction {
+            cost,
+            expression: self.build(root, &choice),
+            optimal: self.steps <= self.max_steps,
+        })
+    }
+
+    /// The choice made by tree extraction, restricted to the classes it uses.
+    fn greedy(
+        &self,
+        root: Id,
+        cost_function: &mut impl NodeCost<L>,
+    ) -> Result<HashMap<Id, Node<L>>, EGraphError> {
+        let extractor = Extractor::new(self.egraph, TreeCost(cost_function));
+        if extractor.find_best_cost(root).is_none() {
+            return Err(EGraphError::Unextractable(root));
+        }
+
+        let mut choice = HashMap::new();
+        let mut todo = vec![root];
+        while let Some(id) = todo.pop() {
+            let id = self.egraph.find(id);
+            if choice.contains_key(&id) {
+                continue;
+            }
+            let node = extractor
+                .find_best_node(id)
+                .expect("children of extractable nodes are extractable")
+                .clone();
+            todo.extend(node.children.iter().copied());
+            choice.insert(id, node);
+        }
+
+        Ok(choice)
+    }
+
+    fn collect_nodes(&mut self, root: Id, cost_function: &mut impl NodeCost<L>) {
+        self.nodes.clear();
+        let mut todo = vec![root];
+        while let Some(id) = todo.pop() {
+            let id = self.egraph.find(id);
+            if self.nodes.contains_key(&id) {
+                continue;
+            }
+
+            let class = self
+                .egraph
+                .get_class(id)
+                .expect("children of classes belong to the graph");
+            let mut nodes: Vec<(usize, Node<L>)> = class
+                .children
+                .iter()
+                .map(|node| (cost_function.node_cost(node), node.clone()))
+                .collect();
+            nodes.sort_by_key(|(cost, _)| *cost);
+
+            for (_, node) in nodes.iter() {
+                todo.extend(node.children.iter().copied());
+            }
+            self.nodes.insert(id, nodes);
+        }
+    }
+
+    fn lower_bound(&self, todo: &[Id], chosen: &HashMap<Id, Node<L>>) -> usize {
+        let mut open: Vec<Id> = todo
+            .iter()
+            .map(|id| self.egraph.find(*id))
+            .filter(|id| !chosen.contains_key(id))
+            .collect();
+        open.sort_unstable();
+        open.dedup();
+        open.iter().map(|id| self.nodes[id][0].0).sum()
+    }
+
+    fn branch(&mut self, mut todo: Vec<Id>, chosen: &mut HashMap<Id, Node<L>>, cost: usize) {
+        if self.steps > self.max_steps {
+            return;
+        }
+
+        let id = loop {
+            match todo.pop() {
+                Some(id) if chosen.contains_key(&self.egraph.find(id)) => continue,
+                Some(id) => break self.egraph.find(id),
+                None => {
+                    if self
+                        .best
+                        .as_ref()
+                        .is_none_or(|(best_cost, _)| cost < *best_cost)
+                    {
+                        self.best = Some((cost, chosen.clone()));
+                    }
+                    return;
+                }
+            }
+        };
+
+        let best_cost = self
+            .best
+            .as_ref()
+            .map_or(usize::MAX, |(best_cost, _)| *best_cost);
+        let mut open = todo.clone();
+        open.push(id);
+        if cost + self.lower_bound(&open, chosen) >= best_cost {
+            return;
+        }
+
+        for index in 0..self.nodes[&id].len() {
+            self.steps += 1;
+            if self.steps > self.max_steps {
+                return;
+            }
+
+            let (node_cost, node) = self.nodes[&id][index].clone();
+            if self.creates_cycle(id, &node, chosen) {
+                continue;
+            }
+
+            let mut child_todo = todo.clone();
+            child_todo.extend(node.children.iter().copied());
+            chosen.insert(id, node);
+            self.branch(child_todo, chosen, cost + node_cost);
+            chosen.remove(&id);
+        }
+    }
+
+    /// Whether choosing `node` for the class `id` would make the class reach
+    /// itself through the nodes chosen so far.
+    fn creates_cycle(&self, id: Id, node: &Node<L>, chosen: &HashMap<Id, Node<L>>) -> bool {
+        let mut visited = HashSet::new();
+        let mut todo: Vec<Id> = node.children.clone();
+        while let Some(child) = todo.pop() {
+            let child = self.egraph.find(child);
+            if child == id {
+                return true;
+            }
+            if !visited.insert(child) {
+                continue;
+            }
+            if let Some(child_node) = chosen.get(&child) {
+                todo.extend(child_node.children.iter().copied());
+            }
+        }
+        false
+    }
+
+    fn build(&self, root: Id, choice: &HashMap<Id, Node<L>>) -> SharedExpression<L> {
+        let mut indices: HashMap<Id, usize> = HashMap::new();
+        let mut expression = SharedExpression { nodes: vec![] };
+        self.build_node(root, choice, &mut indices, &mut expression);
+        expression
+    }
+
+    fn build_node(
+        &self,
+        id: Id,
+        choice: &HashMap<Id, Node<L>>,
+        indices: &mut HashMap<Id, usize>,
+        expression: &mut SharedExpression<L>,
+    ) -> usize {
+        let id = self.egraph.find(id);
+        if let Some(index) = indices.get(&id) {
+            return *index;
+        }
+
+        let node = &choice[&id];
+        let children = node
+            .children
+            .iter()
+            .map(|child| self.build_node(*child, choice, indices, expression))
+            .collect();
+        expression.nodes.push(SharedNode {
+            t: node.t.clone(),
+            children,
+        });
+        indices.insert(id, expression.nodes.len() - 1);
+        expression.nodes.len() - 1
+    }
+}
+
+/// Terms with their costs, cheapest first.
+pub type RankedTerms<L, C> = Vec<(<C as CostFunction<L>>::Cost, Expression<L>)>;
+
+impl<L: Language, N: Analysis<L>> EGraph<L, N> {
+    /// Returns the `k` cheapest distinct terms of the class `id`, cheapest
+    /// first, together with their costs.
+    ///
+    /// Every class keeps a list of its `k` cheapest terms found so far, built
+    /// from the lists of the children, and the lists are refined until none of
+    /// them changes. Since the `k` best terms of a class are built from the
+    /// `k` best terms of its children, this is exact as long as the cost of a
+    /// node never decreases when the cost of a child increases, and it
+    /// terminates on cyclic graphs as long as costs grow with the term.
+    pub fn top_k<C: CostFunction<L>>(
+        &self,
+        id: Id,
+        k: usize,
+        mut cost_function: C,
+    ) -> Result<RankedTerms<L, C>, EGraphError> {
+        let root = self.try_find(id)?;
+        let mut best: HashMap<Id, RankedTerms<L, C>> = HashMap::new();
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            for (id, class) in self.classes() {
+                let mut candidates = best.get(&id).cloned().unwrap_or_default();
+                for node in class.children.iter() {
+                    let child_lists: Vec<&[(C::Cost, Expression<L>)]> = node
+                        .children
+                        .iter()
+                        .map(|child| {
+                            best.get(&self.find(*child))
+                                .map_or(&[][..], |list| &list[..])
+                        })
+                        .collect();
+
+                    for picks in picks(&child_lists) {
+                        let child_costs: Vec<C::Cost> =
+                            picks.iter().map(|(cost, _)| cost.clone()).collect();
+                        let expression = Expression {
+                            t: node.t.clone(),
+                            children: picks
+                                .iter()
+                                .map(|(_, expression)| expression.clone())
+                                .collect(),
+                        };
+                        candidates.push((cost_function.cost(node, &child_costs), expression));
+                    }
+                }
+
+                candidates.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+                let mut seen = HashSet::new();
+                candidates.retain(|(_, expression)| seen.insert(expression.clone()));
+                candidates.truncate(k);
+
+                let old = best.get(&id).map_or(&[][..], |list| &list[..]);
+                if candidates.len() != old.len()
+                    || candidates.iter().zip(old).any(|((_, a), (_, b))| a != b)
+                {
+                    best.insert(id, candidates);
+                    changed = true;
+                }
+            }
+        }
+
+        Ok(best.remove(&root).unwrap_or_default())
+    }
+}
+
+/// Every way of picking one entry from each list.
+fn picks<'l, T>(lists: &[&'l [T]]) -> Vec<Vec<&'l T>> {
+    let Some((first, rest)) = lists.split_first() else {
+        return vec![vec![]];
+    };
+
+    let rest_picks = picks(rest);
+    let mut result = vec![];
+    for item in first.iter() {
+        for rest_pick in rest_picks.iter() {
+            let mut pick = vec![item];
+            pick.extend(rest_pick.iter().copied());
+            result.push(pick);
+        }
+    }
+    result
+}
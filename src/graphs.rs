@@ -1,273 +1,831 @@
 use std::collections::HashMap;
+use std::error::Error;
 use std::fmt;
+use std::hash::Hash;
+
+use crate::analysis::Analysis;
+use crate::explain::{Explain, ExplainUnion, Explanation, Justification, MinimizedExplanation};
+use crate::machine::Program;
+use crate::symbol::Symbol;
+use crate::terms::{largest_term_size, productive_classes, Samples, TermCount, TermCounts, Terms};
+use crate::unionfind::UnionFind;
+
+/// Identifies an e-class of an `EGraph`. Ids are only meaningful for the
+/// graph that handed them out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Id(usize);
+
+impl From<usize> for Id {
+    fn from(index: usize) -> Self {
+        Id(index)
+    }
+}
 
-#[derive(Debug, Clone)]
-pub struct EGraph<'a> {
-    pub children: Vec<EClass<'a>>,
+impl From<Id> for usize {
+    fn from(id: Id) -> Self {
+        id.0
+    }
 }
 
-impl<'a> EGraph<'a> {
-    pub fn init() -> EGraph<'a> {
-        return EGraph { children: vec![] };
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
     }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EGraphError {
+    /// The id does not belong to any class of this graph.
+    UnknownClass { id: Id, number_of_ids: usize },
+    /// A pattern variable was instantiated without being bound.
+    UnboundVariable(Symbol),
+    /// The class only contains infinite terms, so nothing can be extracted.
+    Unextractable(Id),
+    /// The graph has unions that `rebuild` has not processed yet.
+    NotRebuilt,
+    /// An explanation was asked for without enabling explanations first.
+    ExplanationsDisabled,
+    /// An explanation was asked for two terms in different classes.
+    NotEquivalent(Id, Id),
+    /// `pop` was called without a matching `push`.
+    NoCheckpoint,
+}
 
-    pub fn add_expression(&mut self, expression: Expression<'a>) -> usize {
-        for class_index in 0..self.children.len() {
-            if self.class_syntactically_equal_to_expression(&expression, class_index) {
-                return class_index;
+impl fmt::Display for EGraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EGraphError::UnknownClass { id, number_of_ids } => write!(
+                f,
+                "unknown e-class {} (the graph only has ids below {})",
+                id, number_of_ids
+            ),
+            EGraphError::UnboundVariable(x) => write!(f, "pattern variable ?{} is not bound", x),
+            EGraphError::Unextractable(id) => {
+                write!(f, "e-class {} does not represent any finite term", id)
             }
+            EGraphError::NotRebuilt => {
+                write!(f, "the graph has pending unions, call rebuild first")
+            }
+            EGraphError::ExplanationsDisabled => {
+                write!(f, "explanations are not enabled for this graph")
+            }
+            EGraphError::NotEquivalent(a, b) => {
+                write!(f, "e-classes {} and {} are not equivalent", a, b)
+            }
+            EGraphError::NoCheckpoint => write!(f, "there is no checkpoint to go back to"),
         }
+    }
+}
+
+impl Error for EGraphError {}
+
+/// The operators of a language an `EGraph` can be built over. An operator
+/// together with its children forms a `Node` (children are classes) or an
+/// `Expression` (children are expressions). `NodeType` is the arithmetic
+/// language used by default.
+pub trait Language: fmt::Debug + Clone + Eq + Ord + Hash {
+    /// The number of children a node with this operator has.
+    fn arity(&self) -> usize;
+
+    /// The name of the pattern variable if this operator is one. Pattern
+    /// variables only appear in rule patterns and match any class.
+    fn meta_variable(&self) -> Option<Symbol>;
+
+    /// Writes a node with this operator given its already formatted children.
+    fn display(&self, f: &mut fmt::Formatter<'_>, children: &[String]) -> fmt::Result;
+}
+
+#[derive(Debug, Clone)]
+pub struct EGraph<L: Language = NodeType, N: Analysis<L> = ()> {
+    pub analysis: N,
+    children: Vec<EClass<L, N::Data>>,
+    unionfind: UnionFind,
+    memo: HashMap<Node<L>, Id>,
+    pending: Vec<Id>,
+    analysis_pending: Vec<(Node<L>, Id)>,
+    /// The classes each operator was added to. Merged classes keep their
+    /// nodes, so the entries stay valid and only have to be canonicalized.
+    classes_by_operator: HashMap<L, Vec<Id>>,
+    explain: Option<Explain<L>>,
+    /// The changes since the oldest checkpoint, see `push`.
+    undo_log: Vec<Undo<L, N::Data>>,
+    /// The length of the undo log at each checkpoint.
+    checkpoints: Vec<usize>,
+}
+
+/// A change to an `EGraph` that `pop` can undo.
+#[derive(Debug, Clone)]
+enum Undo<L, D> {
+    /// The last class was created for `node`. If `indexed`, the node was also
+    /// added to the memo, the operator index and the parents of its children.
+    Add { node: Node<L>, indexed: bool },
+    /// The class `merged` was merged into `root`, which had `nodes` nodes,
+    /// `parents` parents and the data `data` before.
+    Union {
+        root: Id,
+        merged: Id,
+        nodes: usize,
+        parents: usize,
+        data: D,
+    },
+    /// The data of a class changed.
+    Data { id: Id, data: D },
+    /// The parents of a class were replaced.
+    Parents { id: Id, parents: Vec<(Node<L>, Id)> },
+    /// Parents were appended to a class that had `len` parents before.
+    ParentsExtended { id: Id, len: usize },
+    /// The nodes of a class were replaced.
+    Nodes { id: Id, nodes: Vec<Node<L>> },
+    /// The memo entry of a node changed.
+    Memo { node: Node<L>, id: Option<Id> },
+    ExplainAdd,
+    ExplainUnion(ExplainUnion),
+}
+
+impl<L: Language, N: Analysis<L>> EGraph<L, N> {
+    pub fn init() -> EGraph<L, N>
+    where
+        N: Default,
+    {
+        EGraph::with_analysis(N::default())
+    }
+
+    pub fn with_analysis(analysis: N) -> EGraph<L, N> {
+        EGraph {
+            analysis,
+            children: vec![],
+            unionfind: UnionFind::init(),
+            memo: HashMap::new(),
+            pending: vec![],
+            analysis_pending: vec![],
+            classes_by_operator: HashMap::new(),
+            explain: None,
+            undo_log: vec![],
+            checkpoints: vec![],
+        }
+    }
+
+    /// Records why classes are merged from now on, so that
+    /// `explain_equivalence` can explain it. Must be called before anything
+    /// is added to the graph, and panics otherwise.
+    ///
+    /// With explanations enabled, `add` returns a distinct id for every
+    /// distinct node it is given, even if the node is already present up to
+    /// equivalent children.
+    pub fn with_explanations(mut self) -> EGraph<L, N> {
+        assert!(
+            self.children.is_empty(),
+            "explanations must be enabled on an empty graph"
+        );
+        self.explain = Some(Explain::new());
+        self
+    }
+
+    pub fn are_explanations_enabled(&self) -> bool {
+        self.explain.is_some()
+    }
 
-        let child_indices: Vec<usize> = expression
+    /// Adds an expression to the graph bottom-up, reusing the class of any
+    /// node that is already present.
+    pub fn add_expression(&mut self, expression: Expression<L>) -> Id {
+        let child_ids: Vec<Id> = expression
             .children
             .into_iter()
             .map(|x| self.add_expression(x))
             .collect();
 
+        self.add(Node {
+            t: expression.t,
+            children: child_ids,
+        })
+    }
+
+    /// Adds a single node whose children are class ids, returning the class
+    /// that contains it.
+    ///
+    /// Panics if a child was not handed out by this graph, see `try_add`.
+    pub fn add(&mut self, node: Node<L>) -> Id {
+        debug_assert_eq!(
+            node.children.len(),
+            node.t.arity(),
+            "wrong number of children for {:?}",
+            node.t
+        );
+        for child in node.children.iter() {
+            if let Err(error) = self.try_find(*child) {
+                panic!("cannot add {:?}: {}", node.t, error);
+            }
+        }
+        if let Some(id) = self.explain.as_ref().and_then(|explain| explain.lookup(&node)) {
+            return id;
+        }
+        let original = self.explain.is_some().then(|| node.clone());
+        let node = self.canonicalize(node);
+
+        if let Some(&existing_id) = self.memo.get(&node) {
+            let (Some(explain), Some(original)) = (self.explain.as_mut(), original) else {
+                return self.find(existing_id);
+            };
+
+            // The node is new but congruent to one in the graph. It gets its
+            // own id so that it can be told apart in explanations.
+            let id = self.unionfind.make_set();
+            explain.add(original, id);
+            self.record(Undo::ExplainAdd);
+            let data = N::make(self, &node);
+            self.children.push(EClass {
+                children: vec![node.clone()],
+                parents: vec![],
+                data,
+            });
+            self.record(Undo::Add {
+                node,
+                indexed: false,
+            });
+            self.union_because(id, existing_id, Justification::Congruence)
+                .expect("both ids come from this graph");
+            return id;
+        }
+
+        let data = N::make(self, &node);
+        let id = self.unionfind.make_set();
+        if let (Some(explain), Some(original)) = (self.explain.as_mut(), original) {
+            explain.add(original, id);
+            self.record(Undo::ExplainAdd);
+        }
+        for child in node.children.iter() {
+            self.children[child.0].parents.push((node.clone(), id));
+        }
         self.children.push(EClass {
-            representative: self.children.len(),
-            children: vec![Node {
-                t: expression.t,
-                children: child_indices,
-            }],
+            children: vec![node.clone()],
+            parents: vec![],
+            data,
+        });
+        self.classes_by_operator
+            .entry(node.t.clone())
+            .or_default()
+            .push(id);
+        self.memo.insert(node.clone(), id);
+        self.record(Undo::Add {
+            node,
+            indexed: true,
         });
 
-        return self.children.len() - 1;
+        N::modify(self, id);
+        id
     }
 
-    pub fn class_syntactically_equal_to_expression(
-        &self,
-        expression: &Expression<'a>,
-        class_index: usize,
-    ) -> bool {
-        let class = self.children.get(class_index).unwrap();
-
-        for node in class.children.iter() {
-            if expression.t == node.t && expression.children.len() == node.children.len() {
-                if expression
-                    .children
-                    .clone()
-                    .into_iter()
-                    .zip(node.children.clone())
-                    .all(|(expression, class_index)| {
-                        self.class_syntactically_equal_to_expression(&expression, class_index)
-                    })
-                {
-                    return true;
-                }
-            }
+    /// Same as `add`, but returns an error if a child does not belong to this
+    /// graph.
+    pub fn try_add(&mut self, node: Node<L>) -> Result<Id, EGraphError> {
+        for child in node.children.iter() {
+            self.try_find(*child)?;
         }
+        Ok(self.add(node))
+    }
 
-        return false;
+    /// Looks up the class containing a node without adding it, or returns an
+    /// error if a child does not belong to this graph.
+    pub fn lookup(&self, node: &Node<L>) -> Result<Option<Id>, EGraphError> {
+        let mut node = node.clone();
+        for child in node.children.iter_mut() {
+            *child = self.try_find(*child)?;
+        }
+        Ok(self.memo.get(&node).map(|&id| self.find(id)))
     }
 
-    pub fn union(&mut self, class_index1: usize, class_index2: usize) {
-        if class_index1 == class_index2
-            || class_index1 >= self.children.len()
-            || class_index2 >= self.children.len()
-        {
-            return;
+    fn canonicalize(&self, mut node: Node<L>) -> Node<L> {
+        for child in node.children.iter_mut() {
+            *child = self.find(*child);
         }
+        node
+    }
 
-        let (idx1, idx2) = if class_index1 < class_index2 {
-            (class_index1, class_index2)
-        } else {
-            (class_index2, class_index1)
-        };
+    /// Merges two classes, returning whether they were distinct before.
+    /// Congruence is only restored once `rebuild` is called, so a batch of
+    /// unions should be followed by a rebuild.
+    pub fn union(&mut self, id1: Id, id2: Id) -> Result<bool, EGraphError> {
+        self.union_because(id1, id2, Justification::Asserted)
+    }
 
-        let (before, rest) = self.children.split_at_mut(idx2);
+    /// Same as `union`, recording why the term of `id1` equals the term of
+    /// `id2` if explanations are enabled.
+    pub fn union_because(
+        &mut self,
+        id1: Id,
+        id2: Id,
+        justification: Justification,
+    ) -> Result<bool, EGraphError> {
+        let root1 = self.try_find(id1)?;
+        let root2 = self.try_find(id2)?;
+        if let Some(explain) = self.explain.as_mut() {
+            let undo = explain.union(id1, id2, justification, root1 != root2);
+            self.record(Undo::ExplainUnion(undo));
+        }
+        if root1 == root2 {
+            return Ok(false);
+        }
 
-        let class1 = before.get_mut(idx1).unwrap();
-        let class2 = rest.get_mut(0).unwrap();
-        class1.children.extend(class2.children.clone());
-        class2.representative = class_index1;
-        class2.children = vec![];
-    }
+        let root = self.unionfind.union(root1, root2);
+        let merged = if root == root1 { root2 } else { root1 };
+
+        let mut children = std::mem::take(&mut self.children[merged.0].children);
+        let mut parents = std::mem::take(&mut self.children[merged.0].parents);
 
-    pub fn get_representative_class(&self, class_index: usize) -> &EClass {
-        let mut class_index = class_index;
-        let mut class = self.children.get(class_index).unwrap();
-        while class.representative != class_index {
-            class_index = class.representative;
-            class = self.children.get(class.representative).unwrap();
+        let merged_data = self.children[merged.0].data.clone();
+        let root_data = self.children[root.0].data.clone();
+        self.record(Undo::Union {
+            root,
+            merged,
+            nodes: self.children[root.0].children.len(),
+            parents: self.children[root.0].parents.len(),
+            data: root_data.clone(),
+        });
+        self.analysis
+            .merge(&mut self.children[root.0].data, merged_data.clone());
+        if self.children[root.0].data != root_data {
+            self.analysis_pending
+                .extend(self.children[root.0].parents.iter().cloned());
         }
-        return class;
-    }
+        if self.children[root.0].data != merged_data {
+            self.analysis_pending.extend(parents.iter().cloned());
+        }
+
+        self.children[root.0].children.append(&mut children);
+        self.children[root.0].parents.append(&mut parents);
 
-    pub fn extract_node(&self, node: &Node, max_recursion: usize) -> Vec<Expression> {
-        return self.extract_node_helper(node, max_recursion, 0);
+        self.pending.push(root);
+        N::modify(self, root);
+        Ok(true)
     }
 
-    pub fn extract_node_helper(
-        &self, 
-        node: &Node,
-        max_recursion: usize,
-        current_recursion: usize
-    ) -> Vec<Expression> {
-        let mut expressions = vec![];
+    /// Restores the congruence invariant after a batch of unions: whenever two
+    /// nodes have the same operator and equivalent children, their classes
+    /// are merged, repeating upwards until nothing changes. Analysis data is
+    /// brought up to date the same way. Afterwards every class holds each of
+    /// its canonical nodes exactly once.
+    pub fn rebuild(&mut self) {
+        if self.pending.is_empty() && self.analysis_pending.is_empty() {
+            return;
+        }
 
-        let expression = Expression {
-            t: node.t.clone(),
-            children: vec![],
-        };
+        while !self.pending.is_empty() || !self.analysis_pending.is_empty() {
+            let mut todo: Vec<Id> = std::mem::take(&mut self.pending)
+                .into_iter()
+                .map(|id| self.unionfind.find_mut(id))
+                .collect();
+            todo.sort_unstable();
+            todo.dedup();
 
-        let mut child_expression_lists = vec![];
+            for id in todo {
+                self.repair(id);
+            }
 
-        for child_index in node.children.iter() {
-            let child_expressions =
-                self.extract_all_helper(*child_index, max_recursion, current_recursion + 1);
-            child_expression_lists.push(child_expressions);
+            while let Some((node, id)) = self.analysis_pending.pop() {
+                let node = self.canonicalize(node);
+                let id = self.find(id);
+                let data = N::make(self, &node);
+                let old_data = self.children[id.0].data.clone();
+                self.analysis.merge(&mut self.children[id.0].data, data);
+                if self.children[id.0].data != old_data {
+                    self.record(Undo::Data { id, data: old_data });
+                    self.analysis_pending
+                        .extend(self.children[id.0].parents.iter().cloned());
+                    N::modify(self, id);
+                }
+            }
         }
 
-        let child_expressions_product = cartesian_product(&child_expression_lists);
+        self.rebuild_classes();
+    }
 
-        for child_expressions in child_expressions_product {
-            let mut new_expression = expression.clone();
-            new_expression.children = child_expressions;
-            expressions.push(new_expression.into_owned());
+    fn rebuild_classes(&mut self) {
+        for index in 0..self.children.len() {
+            if self.find(Id(index)) != Id(index) {
+                continue;
+            }
+
+            let old_nodes = std::mem::take(&mut self.children[index].children);
+            let mut nodes: Vec<Node<L>> = old_nodes
+                .iter()
+                .map(|node| self.canonicalize(node.clone()))
+                .collect();
+            nodes.sort_unstable();
+            nodes.dedup();
+
+            if nodes != old_nodes {
+                self.record(Undo::Nodes {
+                    id: Id(index),
+                    nodes: old_nodes,
+                });
+            }
+            self.children[index].children = nodes;
+        }
+    }
+
+    fn repair(&mut self, id: Id) {
+        let id = self.unionfind.find_mut(id);
+        let parents = std::mem::take(&mut self.children[id.0].parents);
+        if self.is_recording() {
+            self.record(Undo::Parents {
+                id,
+                parents: parents.clone(),
+            });
+        }
+
+        for (parent_node, parent_id) in parents.iter() {
+            let removed_id = self.memo.remove(parent_node);
+            let canonical_node = self.canonicalize(parent_node.clone());
+            let replaced_id = self.memo.insert(canonical_node.clone(), *parent_id);
+            if self.is_recording() {
+                self.record(Undo::Memo {
+                    node: parent_node.clone(),
+                    id: removed_id,
+                });
+                self.record(Undo::Memo {
+                    node: canonical_node,
+                    id: replaced_id,
+                });
+            }
+        }
+
+        let mut new_parents: HashMap<Node<L>, Id> = HashMap::new();
+        for (parent_node, parent_id) in parents {
+            let parent_node = self.canonicalize(parent_node);
+            if let Some(&existing_id) = new_parents.get(&parent_node) {
+                self.union_because(existing_id, parent_id, Justification::Congruence)
+                    .expect("parent ids come from this graph");
+            }
+            new_parents.insert(parent_node, parent_id);
         }
 
-        return expressions;
+        let id = self.unionfind.find_mut(id);
+        self.record(Undo::ParentsExtended {
+            id,
+            len: self.children[id.0].parents.len(),
+        });
+        self.children[id.0].parents.extend(new_parents);
     }
 
-    pub fn extract_all(&self, class_index: usize, max_recursion: usize) -> Vec<Expression> {
-        return self.extract_all_helper(class_index, max_recursion, 0);
+    /// Saves a checkpoint that `pop` goes back to. Rebuilds the graph first,
+    /// so that the checkpoint is a consistent state.
+    ///
+    /// Until the checkpoint is popped, every change is recorded in an undo
+    /// log, which makes going back take time proportional to the changes
+    /// since the checkpoint. Path compression is turned off in the meantime.
+    pub fn push(&mut self) {
+        self.rebuild();
+        self.checkpoints.push(self.undo_log.len());
+        self.unionfind.set_compression(false);
     }
 
-    pub fn extract_all_helper(
-        &self,
-        class_index: usize,
-        max_recursion: usize,
-        current_recursion: usize,
-    ) -> Vec<Expression> {
-        if current_recursion > max_recursion {
-            return vec![];
+    /// Undoes every change since the most recent `push`, returning to the
+    /// state at that checkpoint.
+    pub fn pop(&mut self) -> Result<(), EGraphError> {
+        let checkpoint = self.checkpoints.pop().ok_or(EGraphError::NoCheckpoint)?;
+        while self.undo_log.len() > checkpoint {
+            let undo = self.undo_log.pop().expect("the log is longer than the checkpoint");
+            self.undo(undo);
         }
-        let class = self.get_representative_class(class_index);
 
-        let mut expressions = vec![];
+        // The graph was rebuilt when the checkpoint was saved.
+        self.pending.clear();
+        self.analysis_pending.clear();
+        if self.checkpoints.is_empty() {
+            self.unionfind.set_compression(true);
+        }
+        Ok(())
+    }
 
-        for node in class.children.iter() {
-            expressions.extend(self.extract_node_helper(node, max_recursion, current_recursion))
+    fn undo(&mut self, undo: Undo<L, N::Data>) {
+        match undo {
+            Undo::Add { node, indexed } => {
+                self.children.pop();
+                self.unionfind.remove_last();
+                if indexed {
+                    for child in node.children.iter() {
+                        self.children[child.0].parents.pop();
+                    }
+                    self.classes_by_operator
+                        .get_mut(&node.t)
+                        .expect("the operator was indexed")
+                        .pop();
+                    self.memo.remove(&node);
+                }
+            }
+            Undo::Union {
+                root,
+                merged,
+                nodes,
+                parents,
+                data,
+            } => {
+                let root_class = &mut self.children[root.0];
+                let merged_nodes = root_class.children.split_off(nodes);
+                let merged_parents = root_class.parents.split_off(parents);
+                root_class.data = data;
+                self.children[merged.0].children = merged_nodes;
+                self.children[merged.0].parents = merged_parents;
+                self.unionfind.undo_union(root, merged);
+            }
+            Undo::Data { id, data } => self.children[id.0].data = data,
+            Undo::Parents { id, parents } => self.children[id.0].parents = parents,
+            Undo::ParentsExtended { id, len } => self.children[id.0].parents.truncate(len),
+            Undo::Nodes { id, nodes } => self.children[id.0].children = nodes,
+            Undo::Memo { node, id } => match id {
+                Some(id) => {
+                    self.memo.insert(node, id);
+                }
+                None => {
+                    self.memo.remove(&node);
+                }
+            },
+            Undo::ExplainAdd => self
+                .explain
+                .as_mut()
+                .expect("explanations are enabled")
+                .undo_add(),
+            Undo::ExplainUnion(undo) => self
+                .explain
+                .as_mut()
+                .expect("explanations are enabled")
+                .undo_union(undo),
         }
+    }
 
-        return expressions;
+    fn is_recording(&self) -> bool {
+        !self.checkpoints.is_empty()
     }
 
-    pub fn search(&self, pattern: &Expression, max_recursion: usize) -> Vec<(Assignment<'static>, usize)> {
-        let mut needles = vec![];
-    
-        for class_index in 0..self.children.len() {
-            for assignment in self.search_in_class(class_index, pattern, max_recursion) {
-                needles.push((assignment, class_index));
-            }
+    fn record(&mut self, undo: Undo<L, N::Data>) {
+        if self.is_recording() {
+            self.undo_log.push(undo);
         }
-    
-        needles
     }
 
-    pub fn search_in_class(&self, class_index: usize, pattern: &Expression, max_recursion: usize) -> Vec<Assignment<'static>> {
-        let mut needles = vec![];
-        let class = self.get_representative_class(class_index);
+    /// Returns the canonical id of the class containing `id`.
+    ///
+    /// Panics if `id` was not handed out by this graph, see `try_find`.
+    pub fn find(&self, id: Id) -> Id {
+        self.unionfind.find(id)
+    }
 
-        for node in class.children.iter() {
+    /// Returns the canonical id of the class containing `id`, or an error if
+    /// the id does not belong to this graph.
+    pub fn try_find(&self, id: Id) -> Result<Id, EGraphError> {
+        if id.0 >= self.children.len() {
+            return Err(EGraphError::UnknownClass {
+                id,
+                number_of_ids: self.children.len(),
+            });
+        }
+        Ok(self.find(id))
+    }
 
-            match pattern.t {
-                NodeType::MetaVar(x) => {
-                    for expression in self.extract_node(node, max_recursion) {
-                        let mut map: Assignment = HashMap::new();
-                        map.insert(x.to_string(), expression.into_owned());
-                        needles.push(map);
-                    }
-                }
-                _ => {
-                    if pattern.t == node.t && pattern.children.len() == node.children.len() {
+    /// Returns the canonical class containing `id`.
+    pub fn get_class(&self, id: Id) -> Result<&EClass<L, N::Data>, EGraphError> {
+        let id = self.try_find(id)?;
+        Ok(&self.children[id.0])
+    }
 
-                        let mut child_assignments_list = vec![];
+    /// Iterates over the canonical classes together with their ids.
+    pub fn classes(&self) -> impl Iterator<Item = (Id, &EClass<L, N::Data>)> {
+        self.children
+            .iter()
+            .enumerate()
+            .map(|(index, class)| (Id(index), class))
+            .filter(|(id, _)| self.find(*id) == *id)
+    }
 
-                        for (pattern_child, node_child_index) in
-                            pattern.children.iter().zip(node.children.iter())
-                        {
-                            let child_assignments = self.search_in_class(*node_child_index, pattern_child, max_recursion);
-                            
-                            child_assignments_list.push(child_assignments);
-                        }
+    pub fn number_of_classes(&self) -> usize {
+        self.classes().count()
+    }
 
-                        let child_assignments_product = cartesian_product(&child_assignments_list);
+    pub fn number_of_nodes(&self) -> usize {
+        self.classes().map(|(_, class)| class.children.len()).sum()
+    }
 
-                        for child_assignments in child_assignments_product {
+    /// Returns the canonical classes containing a node with operator `t`.
+    pub fn classes_with_operator(&self, t: &L) -> Vec<Id> {
+        let mut ids: Vec<Id> = match self.classes_by_operator.get(t) {
+            Some(ids) => ids.iter().map(|id| self.find(*id)).collect(),
+            None => vec![],
+        };
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
 
-                            let mut assignment: Assignment = HashMap::new();
+    /// Enumerates the distinct terms of the class `id` lazily, smallest first.
+    /// The iterator ends once every term has been produced, and runs forever
+    /// if a cycle gives the class infinitely many terms.
+    pub fn extract_all(&self, id: Id) -> Result<Terms<'_, L, N>, EGraphError> {
+        let id = self.try_find(id)?;
+        Ok(Terms::new(self, id))
+    }
 
-                            let merged_assignment: Option<Assignment> = (|| {
-                                for child_assignment in child_assignments {
-                                    for (key, value) in child_assignment {
-                                        if let Some(existing_value) = assignment.get(&key) {
-                                            if existing_value != &value {
-                                                return None;
-                                            }
-                                        }
-                                        assignment.insert(key, value);
-                                    }
-                                }
+    /// Counts the distinct terms of the class `id` with at most `max_size`
+    /// nodes, or all of its terms if `max_size` is `None`, without building
+    /// them. The graph has to be rebuilt, since the classes of a graph with
+    /// pending unions may hold the same term more than once.
+    pub fn count_terms(&self, id: Id, max_size: Option<usize>) -> Result<TermCount, EGraphError> {
+        let id = self.try_find(id)?;
+        if !self.pending.is_empty() {
+            return Err(EGraphError::NotRebuilt);
+        }
+        let max_size = match max_size {
+            Some(max_size) => max_size,
+            None => match largest_term_size(self, &productive_classes(self), id) {
+                Some(largest) => largest,
+                None => return Ok(TermCount::Infinite),
+            },
+        };
 
-                                return Some(assignment);
-                            })();
+        Ok(TermCount::Finite(TermCounts::new(self, max_size).up_to(id, max_size)))
+    }
 
-                            if let Some(merged_assignment) = merged_assignment {
-                                needles.push(merged_assignment);
-                            }
-                        }
-                    }
-                }
+    /// Draws terms of the class `id` with exactly `size` nodes uniformly at
+    /// random, reproducibly for a given `seed`. Like `count_terms`, this
+    /// needs a rebuilt graph.
+    pub fn sample_terms(
+        &self,
+        id: Id,
+        size: usize,
+        seed: u64,
+    ) -> Result<Samples<'_, L, N>, EGraphError> {
+        let id = self.try_find(id)?;
+        if !self.pending.is_empty() {
+            return Err(EGraphError::NotRebuilt);
+        }
+        Ok(Samples::new(self, id, size, seed))
+    }
+
+    /// Finds every class matching `pattern`, together with the substitution
+    /// binding each pattern variable to a class. The pattern is compiled for
+    /// the e-matching machine first; rules keep their compiled patterns
+    /// around, see `Rule::search`.
+    pub fn search(&self, pattern: &Expression<L>) -> Vec<(Subst, Id)> {
+        Program::compile(pattern).search(self)
+    }
+
+    pub fn search_in_class(
+        &self,
+        id: Id,
+        pattern: &Expression<L>,
+    ) -> Result<Vec<Subst>, EGraphError> {
+        let id = self.try_find(id)?;
+        Ok(Program::compile(pattern).run(self, id))
+    }
+
+    /// Same as `search`, but interprets the pattern directly against every
+    /// class. Kept as a reference for the e-matching machine.
+    #[doc(hidden)]
+    pub fn search_recursive(&self, pattern: &Expression<L>) -> Vec<(Subst, Id)> {
+        let mut needles = vec![];
+
+        for (id, _) in self.classes() {
+            for subst in self.match_in_class(id, pattern, Subst::new()) {
+                needles.push((subst, id));
             }
         }
 
-        return needles;
+        needles
     }
-}
 
-fn cartesian_product<T: Clone>(lists: &[Vec<T>]) -> Vec<Vec<T>> {
-    if lists.is_empty() {
-        return vec![vec![]];
+    /// Extends `subst` in every way that makes `pattern` match the class `id`.
+    /// A variable that is already bound only matches its own class, which is
+    /// how non-linear patterns such as `?a / ?a` are checked.
+    fn match_in_class(&self, id: Id, pattern: &Expression<L>, mut subst: Subst) -> Vec<Subst> {
+        let id = self.find(id);
+
+        if let Some(x) = pattern.t.meta_variable() {
+            return match subst.get(&x) {
+                Some(&bound) if self.find(bound) != id => vec![],
+                Some(_) => vec![subst],
+                None => {
+                    subst.insert(x, id);
+                    vec![subst]
+                }
+            };
+        }
+
+        let mut needles = vec![];
+
+        for node in self.children[id.0].children.iter() {
+            if pattern.t != node.t || pattern.children.len() != node.children.len() {
+                continue;
+            }
+
+            let mut substs = vec![subst.clone()];
+            for (pattern_child, node_child_id) in pattern.children.iter().zip(node.children.iter())
+            {
+                substs = substs
+                    .into_iter()
+                    .flat_map(|subst| self.match_in_class(*node_child_id, pattern_child, subst))
+                    .collect();
+            }
+            needles.extend(substs);
+        }
+
+        needles
     }
 
-    let first_list = &lists[0];
-    let rest_lists = &lists[1..];
+    /// Adds `pattern` to the graph with every pattern variable replaced by the
+    /// class `subst` binds it to, returning the class of the root.
+    pub fn instantiate(&mut self, pattern: &Expression<L>, subst: &Subst) -> Result<Id, EGraphError> {
+        if let Some(x) = pattern.t.meta_variable() {
+            return match subst.get(&x) {
+                Some(&id) => self.try_find(id).map(|_| id),
+                None => Err(EGraphError::UnboundVariable(x)),
+            };
+        }
 
-    let rest_product = cartesian_product(rest_lists);
-    let mut result = Vec::new();
+        let mut child_ids = vec![];
+        for child in pattern.children.iter() {
+            child_ids.push(self.instantiate(child, subst)?);
+        }
 
-    for item in first_list {
-        for product in &rest_product {
-            let mut new_product = vec![item.clone()];
-            new_product.extend_from_slice(product);
-            result.push(new_product);
+        self.try_add(Node {
+            t: pattern.t.clone(),
+            children: child_ids,
+        })
+    }
+
+    /// Explains why two terms are equal as a sequence of rewrites from `a` to
+    /// `b`. Both terms are added to the graph if they are not in it yet.
+    pub fn explain_equivalence(
+        &mut self,
+        a: &Expression<L>,
+        b: &Expression<L>,
+    ) -> Result<Explanation<L>, EGraphError> {
+        if self.explain.is_none() {
+            return Err(EGraphError::ExplanationsDisabled);
+        }
+        let id_a = self.add_expression(a.clone());
+        let id_b = self.add_expression(b.clone());
+        self.rebuild();
+        if self.find(id_a) != self.find(id_b) {
+            return Err(EGraphError::NotEquivalent(id_a, id_b));
         }
+
+        let explain = self.explain.as_ref().expect("explanations are enabled");
+        Ok(explain.explain(id_a, id_b, None))
     }
 
-    result
+    /// Same as `explain_equivalence`, but looks for a short explanation among
+    /// all unions, including those of terms that were already equivalent,
+    /// and simplifies it with `Explanation::simplify`.
+    ///
+    /// A union of terms that were already equivalent is only used if `holds`
+    /// accepts its justification for rewriting the first term into the
+    /// second, for instance with `check_rewrite`.
+    pub fn explain_minimized(
+        &mut self,
+        a: &Expression<L>,
+        b: &Expression<L>,
+        holds: impl Fn(&Justification, &Expression<L>, &Expression<L>) -> bool,
+    ) -> Result<MinimizedExplanation<L>, EGraphError> {
+        let original = self.explain_equivalence(a, b)?;
+        let id_a = self.add_expression(a.clone());
+        let id_b = self.add_expression(b.clone());
+
+        let explain = self.explain.as_ref().expect("explanations are enabled");
+        let shortest = explain.explain(id_a, id_b, Some(&holds)).simplify();
+        let simplified = original.simplify();
+        let explanation = if shortest.len() <= simplified.len() {
+            shortest
+        } else {
+            simplified
+        };
+
+        Ok(MinimizedExplanation {
+            explanation,
+            original_length: original.len(),
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
-pub struct EClass<'a> {
-    representative: usize,
-    pub children: Vec<Node<'a>>,
+pub struct EClass<L, D = ()> {
+    /// The nodes of the class. After a rebuild these are canonical, sorted and
+    /// free of duplicates.
+    pub children: Vec<Node<L>>,
+    parents: Vec<(Node<L>, Id)>,
+    /// The analysis data of the class, see `Analysis`.
+    pub data: D,
 }
 
-impl EClass<'_> {}
-
-#[derive(Debug, Clone)]
-pub struct Node<'a> {
-    pub t: NodeType<'a>,
-    pub children: Vec<usize>,
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Node<L> {
+    pub t: L,
+    pub children: Vec<Id>,
 }
 
-#[derive(Debug, Clone)]
-pub enum NodeType<'a> {
-    MetaVar(&'a str),
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NodeType {
+    MetaVar(Symbol),
     Const(i64),
-    Var(&'a str),
+    Var(Symbol),
     Neg,
     Add,
     Sub,
@@ -275,51 +833,89 @@ pub enum NodeType<'a> {
     Div,
 }
 
-impl<'a> PartialEq for NodeType<'a> {
-    fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (NodeType::MetaVar(a), NodeType::MetaVar(b)) => a == b,
-            (NodeType::Const(a), NodeType::Const(b)) => a == b,
-            (NodeType::Var(a), NodeType::Var(b)) => a == b,
-            (NodeType::Neg, NodeType::Neg) => true,
-            (NodeType::Add, NodeType::Add) => true,
-            (NodeType::Sub, NodeType::Sub) => true,
-            (NodeType::Mul, NodeType::Mul) => true,
-            (NodeType::Div, NodeType::Div) => true,
-            _ => false,
+impl Language for NodeType {
+    fn arity(&self) -> usize {
+        match self {
+            NodeType::MetaVar(_) | NodeType::Const(_) | NodeType::Var(_) => 0,
+            NodeType::Neg => 1,
+            NodeType::Add | NodeType::Sub | NodeType::Mul | NodeType::Div => 2,
         }
     }
-}
 
-impl<'a> Eq for NodeType<'a> {}
+    fn meta_variable(&self) -> Option<Symbol> {
+        match self {
+            NodeType::MetaVar(x) => Some(*x),
+            _ => None,
+        }
+    }
 
-#[derive(Debug, Clone)]
-pub struct Expression<'a> {
-    pub t: NodeType<'a>,
-    pub children: Vec<Expression<'a>>,
+    fn display(&self, f: &mut fmt::Formatter<'_>, children: &[String]) -> fmt::Result {
+        match self {
+            NodeType::MetaVar(s) => write!(f, "?{}", s),
+            NodeType::Const(c) => write!(f, "{}", c),
+            NodeType::Var(v) => write!(f, "{}", v),
+            NodeType::Neg => {
+                if children.len() == 1 {
+                    write!(f, "-({})", children[0])
+                } else {
+                    write!(f, "-(?)") // Should not happen in a well-formed expression
+                }
+            }
+            NodeType::Add => {
+                if children.len() == 2 {
+                    write!(f, "({} + {})", children[0], children[1])
+                } else {
+                    write!(f, "(? + ?)") // Should not happen in a well-formed expression
+                }
+            }
+            NodeType::Sub => {
+                if children.len() == 2 {
+                    write!(f, "({} - {})", children[0], children[1])
+                } else {
+                    write!(f, "(? - ?)") // Should not happen in a well-formed expression
+                }
+            }
+            NodeType::Mul => {
+                if children.len() == 2 {
+                    write!(f, "({} * {})", children[0], children[1])
+                } else {
+                    write!(f, "(? * ?)") // Should not happen in a well-formed expression
+                }
+            }
+            NodeType::Div => {
+                if children.len() == 2 {
+                    write!(f, "({} / {})", children[0], children[1])
+                } else {
+                    write!(f, "(? / ?)") // Should not happen in a well-formed expression
+                }
+            }
+        }
+    }
 }
 
-impl<'a> PartialEq for Expression<'a> {
-    fn eq(&self, other: &Self) -> bool {
-        self.t == other.t && self.children == other.children
-    }
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Expression<L = NodeType> {
+    pub t: L,
+    pub children: Vec<Expression<L>>,
 }
 
-impl<'a> Eq for Expression<'a> {}
+/// Binds pattern variables to the terms they matched.
+pub type Assignment<L> = HashMap<Symbol, Expression<L>>;
 
-type Assignment<'a> = HashMap<String, Expression<'a>>;
+/// Binds pattern variables to the classes they matched.
+pub type Subst = HashMap<Symbol, Id>;
 
-impl<'a> Expression<'a> {
-    pub fn structural_match(&self, expression: &Expression<'a>) -> Option<Assignment<'a>> {
-        match &self.t {
-            NodeType::MetaVar(x) => {
-                let mut map: Assignment = HashMap::new();
-                map.insert(x.to_string(), expression.clone());
+impl<L: Language> Expression<L> {
+    pub fn structural_match(&self, expression: &Expression<L>) -> Option<Assignment<L>> {
+        match self.t.meta_variable() {
+            Some(x) => {
+                let mut map: Assignment<L> = HashMap::new();
+                map.insert(x, expression.clone());
                 return Some(map);
             }
             _ => {
                 if self.t == expression.t && self.children.len() == expression.children.len() {
-                    let mut assignment: Assignment = HashMap::new();
+                    let mut assignment: Assignment<L> = HashMap::new();
                     for (pattern_child, expression_child) in
                         self.children.iter().zip(expression.children.iter())
                     {
@@ -344,14 +940,14 @@ impl<'a> Expression<'a> {
         None
     }
 
-    pub fn apply_assignment(&self, assignment: &Assignment<'a>) -> Expression<'a> {
-        match &self.t {
-            NodeType::MetaVar(x) => match assignment.get(&x.to_string()) {
+    pub fn apply_assignment(&self, assignment: &Assignment<L>) -> Expression<L> {
+        match self.t.meta_variable() {
+            Some(x) => match assignment.get(&x) {
                 Some(expr) => expr.clone(),
                 _ => self.clone(),
             },
             _ => {
-                let new_children: Vec<Expression<'a>> = self
+                let new_children: Vec<Expression<L>> = self
                     .children
                     .iter()
                     .map(|child| child.apply_assignment(assignment))
@@ -364,6 +960,22 @@ impl<'a> Expression<'a> {
         }
     }
 
+    /// The subterm at a position given as the child indices leading to it
+    /// from the root, if the position exists.
+    pub fn subterm(&self, position: &[usize]) -> Option<&Expression<L>> {
+        position
+            .iter()
+            .try_fold(self, |expression, i| expression.children.get(*i))
+    }
+
+    pub fn subterm_mut(&mut self, position: &[usize]) -> Option<&mut Expression<L>> {
+        position
+            .iter()
+            .try_fold(self, |expression, i| expression.children.get_mut(*i))
+    }
+}
+
+impl Expression<NodeType> {
     pub fn const_eval(&self) -> Option<i64> {
         match self.t {
             NodeType::Const(c) => Some(c),
@@ -392,8 +1004,8 @@ impl<'a> Expression<'a> {
                                 NodeType::Sub => left_val.checked_sub(right_val),
                                 NodeType::Mul => left_val.checked_mul(right_val),
                                 NodeType::Div => {
-                                    if left_val % right_val == 0 {
-                                        Some(left_val / right_val)
+                                    if left_val.checked_rem(right_val)? == 0 {
+                                        left_val.checked_div(right_val)
                                     } else {
                                         None
                                     }
@@ -410,19 +1022,6 @@ impl<'a> Expression<'a> {
         }
     }
 
-    pub fn into_owned(self) -> Expression<'static> {
-        match self.t {
-            NodeType::MetaVar(s) => Expression { t: NodeType::MetaVar(Box::leak(s.to_string().into_boxed_str())), children: self.children.into_iter().map(|c| c.into_owned()).collect() },
-            NodeType::Const(c) => Expression { t: NodeType::Const(c), children: self.children.into_iter().map(|c| c.into_owned()).collect() },
-            NodeType::Var(s) => Expression { t: NodeType::Var(Box::leak(s.to_string().into_boxed_str())), children: self.children.into_iter().map(|c| c.into_owned()).collect() },
-            NodeType::Neg => Expression { t: NodeType::Neg, children: self.children.into_iter().map(|c| c.into_owned()).collect() },
-            NodeType::Add => Expression { t: NodeType::Add, children: self.children.into_iter().map(|c| c.into_owned()).collect() },
-            NodeType::Sub => Expression { t: NodeType::Sub, children: self.children.into_iter().map(|c| c.into_owned()).collect() },
-            NodeType::Mul => Expression { t: NodeType::Mul, children: self.children.into_iter().map(|c| c.into_owned()).collect() },
-            NodeType::Div => Expression { t: NodeType::Div, children: self.children.into_iter().map(|c| c.into_owned()).collect() },
-        }
-    }
-
     /// Creates a new constant expression.
     pub fn constant(value: i64) -> Self {
         Expression {
@@ -432,17 +1031,17 @@ impl<'a> Expression<'a> {
     }
 
     /// Creates a new variable expression.
-    pub fn variable(name: &'a str) -> Self {
+    pub fn variable(name: &str) -> Self {
         Expression {
-            t: NodeType::Var(name),
+            t: NodeType::Var(Symbol::new(name)),
             children: Vec::new(),
         }
     }
 
     /// Creates a new meta-variable expression.
-    pub fn meta_variable(name: &'a str) -> Self {
+    pub fn meta_variable(name: &str) -> Self {
         Expression {
-            t: NodeType::MetaVar(name),
+            t: NodeType::MetaVar(Symbol::new(name)),
             children: Vec::new(),
         }
     }
@@ -456,6 +1055,7 @@ impl<'a> Expression<'a> {
     }
 
     /// Creates a new addition expression.
+    #[allow(clippy::should_implement_trait)]
     pub fn add(left: Self, right: Self) -> Self {
         Expression {
             t: NodeType::Add,
@@ -488,47 +1088,116 @@ impl<'a> Expression<'a> {
     }
 }
 
-impl<'a> fmt::Display for Expression<'a> {
+impl<L: Language> fmt::Display for Expression<L> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match &self.t {
-            NodeType::MetaVar(s) => write!(f, "?{}", s),
-            NodeType::Const(c) => write!(f, "{}", c),
-            NodeType::Var(v) => write!(f, "{}", v),
-            NodeType::Neg => {
-                if self.children.len() == 1 {
-                    write!(f, "-({})", self.children[0])
-                } else {
-                    write!(f, "-(?)") // Should not happen in a well-formed expression
-                }
-            }
-            NodeType::Add => {
-                if self.children.len() == 2 {
-                    write!(f, "({} + {})", self.children[0], self.children[1])
-                } else {
-                    write!(f, "(? + ?)") // Should not happen in a well-formed expression
-                }
-            }
-            NodeType::Sub => {
-                if self.children.len() == 2 {
-                    write!(f, "({} - {})", self.children[0], self.children[1])
-                } else {
-                    write!(f, "(? - ?)") // Should not happen in a well-formed expression
-                }
-            }
-            NodeType::Mul => {
-                if self.children.len() == 2 {
-                    write!(f, "({} * {})", self.children[0], self.children[1])
-                } else {
-                    write!(f, "(? * ?)") // Should not happen in a well-formed expression
-                }
+        let children: Vec<String> = self.children.iter().map(|child| child.to_string()).collect();
+        self.t.display(f, &children)
+    }
+}
+
+/// A term stored as a DAG, so that shared subterms are only stored once. Every
+/// node refers to its children by their position in `nodes`, children always
+/// come before their parents, and the last node is the root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedExpression<L = NodeType> {
+    pub nodes: Vec<SharedNode<L>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SharedNode<L> {
+    pub t: L,
+    pub children: Vec<usize>,
+}
+
+impl<L: Language> SharedExpression<L> {
+    /// Unfolds the shared subterms into a plain expression. Panics if there
+    /// are no nodes.
+    pub fn to_expression(&self) -> Expression<L> {
+        self.expression_at(self.nodes.len() - 1)
+    }
+
+    fn expression_at(&self, index: usize) -> Expression<L> {
+        let node = &self.nodes[index];
+        Expression {
+            t: node.t.clone(),
+            children: node
+                .children
+                .iter()
+                .map(|child| self.expression_at(*child))
+                .collect(),
+        }
+    }
+
+    /// Which nodes get their own `let` binding: those that are not leaves and
+    /// are used more than once.
+    fn bound(&self) -> Vec<bool> {
+        let mut uses = vec![0; self.nodes.len()];
+        for node in self.nodes.iter() {
+            for child in node.children.iter() {
+                uses[*child] += 1;
             }
-            NodeType::Div => {
-                if self.children.len() == 2 {
-                    write!(f, "({} / {})", self.children[0], self.children[1])
+        }
+
+        self.nodes
+            .iter()
+            .zip(uses)
+            .map(|(node, uses)| !node.children.is_empty() && uses > 1)
+            .collect()
+    }
+}
+
+struct SharedNodeDisplay<'a, L> {
+    expression: &'a SharedExpression<L>,
+    bound: &'a [bool],
+    index: usize,
+}
+
+impl<L: Language> fmt::Display for SharedNodeDisplay<'_, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let node = &self.expression.nodes[self.index];
+        let children: Vec<String> = node
+            .children
+            .iter()
+            .map(|child| {
+                if self.bound[*child] {
+                    format!("v{}", child)
                 } else {
-                    write!(f, "(? / ?)") // Should not happen in a well-formed expression
+                    SharedNodeDisplay {
+                        index: *child,
+                        ..*self
+                    }
+                    .to_string()
                 }
+            })
+            .collect();
+        node.t.display(f, &children)
+    }
+}
+
+/// Writes shared subterms as `let` bindings, e.g. `let v1 = (x * 2) in (v1 + v1)`.
+impl<L: Language> fmt::Display for SharedExpression<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.nodes.is_empty() {
+            return Ok(());
+        }
+
+        let bound = self.bound();
+        for index in 0..self.nodes.len() - 1 {
+            if bound[index] {
+                let node = SharedNodeDisplay {
+                    expression: self,
+                    bound: &bound,
+                    index,
+                };
+                write!(f, "let v{} = {} in ", index, node)?;
             }
         }
+
+        let root = SharedNodeDisplay {
+            expression: self,
+            bound: &bound,
+            index: self.nodes.len() - 1,
+        };
+        write!(f, "{}", root)
     }
-}
\ No newline at end of file
+}
@@ -1,2 +1,11 @@
-mod graphs;
-mod rules;
\ No newline at end of file
+pub mod analysis;
+pub mod explain;
+pub mod extract;
+pub mod graphs;
+pub mod machine;
+pub mod rules;
+pub mod runner;
+pub mod scheduler;
+pub mod symbol;
+pub mod terms;
+pub mod unionfind;
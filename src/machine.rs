@@ -0,0 +1,144 @@
+use crate::analysis::Analysis;
+use crate::graphs::*;
+use crate::symbol::Symbol;
+
+/// A register of the machine. Every register holds a class id.
+type Reg = usize;
+
+#[derive(Debug, Clone)]
+enum Instruction<L> {
+    /// For every node in the class in register `i` with operator `t` and
+    /// `arity` children, writes the children to the registers starting at
+    /// `out` and continues.
+    Bind {
+        i: Reg,
+        t: L,
+        arity: usize,
+        out: Reg,
+    },
+    /// Continues only if the registers `i` and `j` hold the same class.
+    Compare { i: Reg, j: Reg },
+    /// Reports a match, reading each pattern variable from its register.
+    Yield { variables: Vec<(Symbol, Reg)> },
+}
+
+/// A pattern compiled into instructions for the e-matching machine. Compiling
+/// once and running the program against many classes avoids re-interpreting
+/// the pattern tree for every class.
+#[derive(Debug, Clone)]
+pub struct Program<L> {
+    instructions: Vec<Instruction<L>>,
+    number_of_registers: usize,
+    /// The operator of the pattern root, or `None` if the root is a pattern
+    /// variable and every class matches.
+    root: Option<L>,
+}
+
+impl<L: Language> Program<L> {
+    pub fn compile(pattern: &Expression<L>) -> Program<L> {
+        let mut instructions = vec![];
+        let mut variables: Vec<(Symbol, Reg)> = vec![];
+        let mut number_of_registers = 1;
+        let mut todo = vec![(pattern, 0)];
+
+        while let Some((pattern, reg)) = todo.pop() {
+            if let Some(x) = pattern.t.meta_variable() {
+                match variables.iter().find(|(y, _)| *y == x) {
+                    Some(&(_, bound)) => {
+                        instructions.push(Instruction::Compare { i: bound, j: reg })
+                    }
+                    None => variables.push((x, reg)),
+                }
+                continue;
+            }
+
+            let out = number_of_registers;
+            number_of_registers += pattern.children.len();
+            instructions.push(Instruction::Bind {
+                i: reg,
+                t: pattern.t.clone(),
+                arity: pattern.children.len(),
+                out,
+            });
+
+            for (offset, child) in pattern.children.iter().enumerate().rev() {
+                todo.push((child, out + offset));
+            }
+        }
+
+        instructions.push(Instruction::Yield { variables });
+
+        Program {
+            instructions,
+            number_of_registers,
+            root: match pattern.t.meta_variable() {
+                Some(_) => None,
+                None => Some(pattern.t.clone()),
+            },
+        }
+    }
+
+    /// Finds every class the pattern matches, only visiting classes that
+    /// contain the root operator.
+    pub fn search<N: Analysis<L>>(&self, egraph: &EGraph<L, N>) -> Vec<(Subst, Id)> {
+        let ids: Vec<Id> = match &self.root {
+            Some(t) => egraph.classes_with_operator(t),
+            None => egraph.classes().map(|(id, _)| id).collect(),
+        };
+
+        let mut needles = vec![];
+        for id in ids {
+            for subst in self.run(egraph, id) {
+                needles.push((subst, id));
+            }
+        }
+
+        needles
+    }
+
+    /// Runs the program against a single class.
+    pub fn run<N: Analysis<L>>(&self, egraph: &EGraph<L, N>, id: Id) -> Vec<Subst> {
+        let mut registers = vec![egraph.find(id); self.number_of_registers];
+        let mut needles = vec![];
+        self.execute(egraph, 0, &mut registers, &mut needles);
+        needles
+    }
+
+    fn execute<N: Analysis<L>>(
+        &self,
+        egraph: &EGraph<L, N>,
+        pc: usize,
+        registers: &mut [Id],
+        needles: &mut Vec<Subst>,
+    ) {
+        match &self.instructions[pc] {
+            Instruction::Bind { i, t, arity, out } => {
+                let class = egraph
+                    .get_class(registers[*i])
+                    .expect("registers only hold ids from this graph");
+                for node in class.children.iter() {
+                    if node.t != *t || node.children.len() != *arity {
+                        continue;
+                    }
+                    for (offset, child) in node.children.iter().enumerate() {
+                        registers[out + offset] = egraph.find(*child);
+                    }
+                    self.execute(egraph, pc + 1, registers, needles);
+                }
+            }
+            Instruction::Compare { i, j } => {
+                if egraph.find(registers[*i]) == egraph.find(registers[*j]) {
+                    self.execute(egraph, pc + 1, registers, needles);
+                }
+            }
+            Instruction::Yield { variables } => {
+                needles.push(
+                    variables
+                        .iter()
+                        .map(|&(x, reg)| (x, registers[reg]))
+                        .collect(),
+                );
+            }
+        }
+    }
+}
@@ -1,66 +1,71 @@
-mod graphs;
-mod rules;
+use egraphs::analysis::ConstantFolding;
+use egraphs::extract::*;
+use egraphs::graphs::*;
+use egraphs::rules::*;
+use egraphs::runner::*;
 
-use graphs::*;
-use rules::*;
-
-fn main() {
-    let mut graph = EGraph::init();
+fn main() -> Result<(), EGraphError> {
+    let mut graph: EGraph<NodeType, ConstantFolding> = EGraph::init().with_explanations();
     let expression = Expression::divide(Expression::multiply(Expression::variable("x"), Expression::constant(-6)), Expression::multiply(Expression::variable("x"), Expression::constant(-3)));
 
-    let index = graph.add_expression(expression);
+    let index = graph.add_expression(expression.clone());
 
     println!("{:?}", index);
-    let node = graph.children.get(index).unwrap();
+    let node = graph.get_class(index)?;
 
     println!("{:?}", node);
-    println!("{:?}", graph.extract_all(index, 10));
-
-    let mut graph_copy = graph.clone();
-    
-    for i in 0..4 {
-        println!("iteration {}", i);
-
-        let mut matches = vec![];
-        graph_copy = graph.clone();
+    println!("{:?}", graph.extract_all(index)?.collect::<Vec<_>>());
 
-        for rule in Rule::rules() {
-            let search_results = graph_copy.search(&rule.lhs, 3);
-            for (assignment, eclass_index) in search_results {
-                matches.push((rule.rhs.clone(), assignment, eclass_index));
-            }
-        }
-
-        for (assignment, eclass_index) in graph_copy.search(&Expression::meta_variable("a"), 3) {
-            let expression = Expression::meta_variable("a").apply_assignment(&assignment);
-            if let Some(const_value) = expression.const_eval() {
-                let eclass_index2 = graph.add_expression(Expression::constant(const_value));
-                graph.union(eclass_index2, eclass_index);
-
-                if const_value < 0 {
-                    let eclass_index3 = graph.add_expression(Expression::negate(Expression::constant(-const_value)));
-                    graph.union(eclass_index3, eclass_index);
-                }
-            }
-        }
+    let mut runner = Runner::new(graph, Limits { iterations: 4, ..Limits::default() });
+    let rules = Rule::rules();
+    let stop_reason = runner.run(&rules)?;
+    for (i, iteration) in runner.iterations.iter().enumerate() {
+        println!("iteration {}: {} nodes in {} classes", i, iteration.number_of_nodes, iteration.number_of_classes);
+    }
+    println!("stopped: {}", stop_reason);
 
-        for (pattern, assignment, eclass_index) in matches {
-            let eclass_index2 = graph.add_expression(pattern.apply_assignment(&assignment));
-            graph.union(eclass_index2, eclass_index);
-        }
+    let mut graph = runner.egraph;
+    let (cost, best) = Extractor::new(&graph, AstSize).find_best(index)?;
+    println!("best: {} (size {})", best, cost);
+    let holds = |justification: &_, from: &_, to: &_| check_rewrite(&rules, justification, from, to).is_ok();
+    let minimized = graph.explain_minimized(&expression, &best, holds)?;
+    println!("{}", minimized);
+    match check_proof(&rules, &minimized.explanation) {
+        Ok(()) => println!("proof checked"),
+        Err(invalid) => println!("{}", invalid),
+    }
+    let shared = DagExtractor::new(&graph, 10_000).extract(index, AstSize)?;
+    println!("best shared: {} (size {})", shared.expression, shared.cost);
+    for (cost, expression) in graph.top_k(index, 5, AstSize)? {
+        println!("top: {} (size {})", expression, cost);
     }
 
-    for expression in graph.extract_all(index, 2) {
+    println!("terms of size at most 7: {}", graph.count_terms(index, Some(7))?);
+    println!("terms: {}", graph.count_terms(index, None)?);
+    for expression in graph.sample_terms(index, 7, 0)?.take(3) {
+        println!("sample: {}", expression);
+    }
+    for expression in graph.extract_all(index)?.take(20) {
         println!("{}", expression);
     }
 
+    let limits = Limits { iterations: 8, nodes: 1_000, ..Limits::default() };
+    let two = Expression::constant(2);
+    println!("{} = {}: {}", expression, best, graph.prove_equal(&expression, &best, &rules, limits.clone())?);
+    println!("{} = {}: {}", expression, two, graph.prove_equal(&expression, &two, &rules, limits.clone())?);
+    let mut small: EGraph<NodeType, ConstantFolding> = EGraph::init();
+    let (a, b) = (Expression::add(Expression::variable("y"), Expression::constant(0)), Expression::multiply(Expression::variable("y"), Expression::constant(2)));
+    println!("{} = {}: {}", a, b, small.prove_equal(&a, &b, &rules, limits)?);
+
+    println!("before checkpoint: {} nodes in {} classes", graph.number_of_nodes(), graph.number_of_classes());
+    graph.push();
+    let three = graph.add_expression(Expression::constant(3));
+    graph.union(index, three)?;
+    graph.rebuild();
+    println!("assuming {} = 3: {} nodes in {} classes", best, graph.number_of_nodes(), graph.number_of_classes());
+    graph.pop()?;
+    println!("rolled back: {} nodes in {} classes", graph.number_of_nodes(), graph.number_of_classes());
+
     println!("----------");
-    /* 
-    let pattern = Expression::divide(Expression::meta_variable("a"), Expression::meta_variable("b"));
-    for (assignment, class_index) in graph.search(&pattern, 1) {
-        let expression = pattern.apply_assignment(&assignment);
-        println!("{} {}", expression, class_index);
-    }
-    */
-    
+    Ok(())
 }
@@ -1,141 +1,587 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::analysis::Analysis;
+use crate::explain::{Explanation, Justification, ProofStep};
 use crate::graphs::*;
+use crate::machine::Program;
+use crate::symbol::Symbol;
+
+/// A condition on a match of the left-hand side of a rule, given the matched
+/// class and the substitution. Closures taking the same arguments are
+/// conditions too.
+pub trait Condition<L: Language, N: Analysis<L>> {
+    fn check(&self, egraph: &EGraph<L, N>, id: Id, subst: &Subst) -> bool;
+
+    /// Rebinds variables of a match to the nodes the condition relied on, such
+    /// as the constant a class was known to equal, so that explanations show
+    /// why the condition held. Does nothing by default.
+    fn rebind(&self, _egraph: &mut EGraph<L, N>, _subst: &mut Subst) {}
+
+    /// Checks the condition on a match of the left-hand side in a term, given
+    /// the subterm bound to each variable, for `check_proof`. Returns `None`
+    /// if the condition cannot be checked without the graph, as for closures.
+    fn check_term(&self, _assignment: &Assignment<L>) -> Option<bool> {
+        None
+    }
+}
+
+impl<L, N, F> Condition<L, N> for F
+where
+    L: Language,
+    N: Analysis<L>,
+    F: Fn(&EGraph<L, N>, Id, &Subst) -> bool,
+{
+    fn check(&self, egraph: &EGraph<L, N>, id: Id, subst: &Subst) -> bool {
+        self(egraph, id, subst)
+    }
+}
+
+/// Holds if the class bound to a variable is known to be a constant other
+/// than zero.
+#[derive(Debug, Clone)]
+pub struct NonZero(pub Symbol);
+
+impl NonZero {
+    pub fn new(name: &str) -> NonZero {
+        NonZero(Symbol::new(name))
+    }
+}
+
+impl<N: Analysis<NodeType>> Condition<NodeType, N> for NonZero {
+    fn check(&self, egraph: &EGraph<NodeType, N>, _id: Id, subst: &Subst) -> bool {
+        constant_value(egraph, subst, self.0).is_some_and(|value| value != 0)
+    }
+
+    fn rebind(&self, egraph: &mut EGraph<NodeType, N>, subst: &mut Subst) {
+        bind_constant(egraph, subst, self.0);
+    }
+
+    fn check_term(&self, assignment: &Assignment<NodeType>) -> Option<bool> {
+        let value = assignment.get(&self.0).and_then(Expression::const_eval);
+        Some(value.is_some_and(|value| value != 0))
+    }
+}
+
+/// Holds if the class bound to a variable is known to be a positive constant.
+#[derive(Debug, Clone)]
+pub struct PositiveConstant(pub Symbol);
+
+impl PositiveConstant {
+    pub fn new(name: &str) -> PositiveConstant {
+        PositiveConstant(Symbol::new(name))
+    }
+}
+
+impl<N: Analysis<NodeType>> Condition<NodeType, N> for PositiveConstant {
+    fn check(&self, egraph: &EGraph<NodeType, N>, _id: Id, subst: &Subst) -> bool {
+        constant_value(egraph, subst, self.0).is_some_and(|value| value > 0)
+    }
+
+    fn rebind(&self, egraph: &mut EGraph<NodeType, N>, subst: &mut Subst) {
+        bind_constant(egraph, subst, self.0);
+    }
+
+    fn check_term(&self, assignment: &Assignment<NodeType>) -> Option<bool> {
+        let value = assignment.get(&self.0).and_then(Expression::const_eval);
+        Some(value.is_some_and(|value| value > 0))
+    }
+}
+
+/// The value of a constant node in the class bound to `x`, if there is one.
+fn constant_value<N: Analysis<NodeType>>(
+    egraph: &EGraph<NodeType, N>,
+    subst: &Subst,
+    x: Symbol,
+) -> Option<i64> {
+    let class = egraph.get_class(*subst.get(&x)?).ok()?;
+    class.children.iter().find_map(|node| match node.t {
+        NodeType::Const(value) => Some(value),
+        _ => None,
+    })
+}
+
+/// Binds `x` to the constant node `constant_value` found, if there is one.
+fn bind_constant<N: Analysis<NodeType>>(
+    egraph: &mut EGraph<NodeType, N>,
+    subst: &mut Subst,
+    x: Symbol,
+) {
+    if let Some(value) = constant_value(egraph, subst, x) {
+        subst.insert(x, egraph.add_expression(Expression::constant(value)));
+    }
+}
+
+/// Computes what a match of the left-hand side of a rule is equal to.
+pub trait Applier<L: Language, N: Analysis<L>> {
+    /// Adds whatever the match in class `id` is equal to, returning the
+    /// classes to merge with `id`.
+    fn apply(
+        &self,
+        egraph: &mut EGraph<L, N>,
+        id: Id,
+        subst: &Subst,
+    ) -> Result<Vec<Id>, EGraphError>;
+
+    /// Rebinds variables of a match to the nodes the applier relied on, see
+    /// `Condition::rebind`. Does nothing by default.
+    fn rebind(&self, _egraph: &mut EGraph<L, N>, _subst: &mut Subst) {}
+
+    /// Computes what a match of the left-hand side in a term is equal to,
+    /// given the subterm bound to each variable, for `check_proof`. Returns
+    /// `None` if the applier cannot do this without the graph, as for
+    /// closures.
+    fn apply_term(&self, _assignment: &Assignment<L>) -> Option<Vec<Expression<L>>> {
+        None
+    }
+}
+
+/// A pattern is instantiated with the substitution of the match.
+impl<L: Language, N: Analysis<L>> Applier<L, N> for Expression<L> {
+    fn apply(
+        &self,
+        egraph: &mut EGraph<L, N>,
+        _id: Id,
+        subst: &Subst,
+    ) -> Result<Vec<Id>, EGraphError> {
+        Ok(vec![egraph.instantiate(self, subst)?])
+    }
+
+    fn apply_term(&self, assignment: &Assignment<L>) -> Option<Vec<Expression<L>>> {
+        Some(vec![self.apply_assignment(assignment)])
+    }
+}
+
+impl<L, N, F> Applier<L, N> for F
+where
+    L: Language,
+    N: Analysis<L>,
+    F: Fn(&mut EGraph<L, N>, Id, &Subst) -> Result<Vec<Id>, EGraphError>,
+{
+    fn apply(
+        &self,
+        egraph: &mut EGraph<L, N>,
+        id: Id,
+        subst: &Subst,
+    ) -> Result<Vec<Id>, EGraphError> {
+        self(egraph, id, subst)
+    }
+}
+
+pub struct Rule<L = NodeType, N = ()> {
+    pub name: String,
+    pub lhs: Expression<L>,
+    pub rhs: Box<dyn Applier<L, N>>,
+    program: Program<L>,
+    conditions: Vec<Box<dyn Condition<L, N>>>,
+}
 
-pub struct Rule<'a> {
-    pub lhs: Expression<'a>,
-    pub rhs: Expression<'a>
-}
-
-impl<'a> Rule<'a> {
-
-    pub fn rules() -> Vec<Rule<'a>> {
-        return vec![
-            // Commutativity of addition
-            Rule {
-                lhs: Expression::add(Expression::meta_variable("a"), Expression::meta_variable("b")),
-                rhs: Expression::add(Expression::meta_variable("b"), Expression::meta_variable("a")),
-            },
-            // Identity element of addition
-            Rule {
-                lhs: Expression::add(Expression::meta_variable("a"), Expression::constant(0)),
-                rhs: Expression::meta_variable("a"),
-            },
-            // Commutativity of multiplication
-            Rule {
-                lhs: Expression::multiply(Expression::meta_variable("a"), Expression::meta_variable("b")),
-                rhs: Expression::multiply(Expression::meta_variable("b"), Expression::meta_variable("a")),
-            },
-            // Identity element of multiplication
-            Rule {
-                lhs: Expression::multiply(Expression::meta_variable("a"), Expression::constant(1)),
-                rhs: Expression::meta_variable("a"),
-            },
-            // Multiplication by zero
-            Rule {
-                lhs: Expression::multiply(Expression::meta_variable("a"), Expression::constant(0)),
-                rhs: Expression::constant(0),
-            },
-            // Associativity of addition
-            Rule {
-                lhs: Expression::add(
+impl<L: Language, N: Analysis<L>> Rule<L, N> {
+    /// Creates a rule rewriting matches of `lhs` with `rhs`, which is either a
+    /// pattern or any other `Applier`.
+    pub fn new(
+        name: &str,
+        lhs: Expression<L>,
+        rhs: impl Applier<L, N> + 'static,
+    ) -> Rule<L, N> {
+        let program = Program::compile(&lhs);
+        Rule {
+            name: name.to_string(),
+            lhs,
+            rhs: Box::new(rhs),
+            program,
+            conditions: vec![],
+        }
+    }
+
+    /// Only applies the rule to matches for which `condition` holds, in
+    /// addition to any conditions added before.
+    pub fn with_condition(mut self, condition: impl Condition<L, N> + 'static) -> Rule<L, N> {
+        self.conditions.push(Box::new(condition));
+        self
+    }
+
+    /// Finds every match of the left-hand side using the pattern compiled when
+    /// the rule was created.
+    pub fn search(&self, egraph: &EGraph<L, N>) -> Vec<(Subst, Id)> {
+        self.program.search(egraph)
+    }
+
+    /// Whether every condition of the rule holds for a match.
+    pub fn check(&self, egraph: &EGraph<L, N>, id: Id, subst: &Subst) -> bool {
+        self.conditions
+            .iter()
+            .all(|condition| condition.check(egraph, id, subst))
+    }
+
+    /// Rebinds the variables of a match to the nodes the conditions and the
+    /// right-hand side relied on, see `Condition::rebind`.
+    pub fn rebind(&self, egraph: &mut EGraph<L, N>, subst: &mut Subst) {
+        for condition in self.conditions.iter() {
+            condition.rebind(egraph, subst);
+        }
+        self.rhs.rebind(egraph, subst);
+    }
+}
+
+impl<N: Analysis<NodeType> + 'static> Rule<NodeType, N> {
+
+    pub fn rules() -> Vec<Rule<NodeType, N>> {
+        vec![
+            Rule::new(
+                "Commutativity of addition",
+                Expression::add(Expression::meta_variable("a"), Expression::meta_variable("b")),
+                Expression::add(Expression::meta_variable("b"), Expression::meta_variable("a")),
+            ),
+            Rule::new(
+                "Identity element of addition",
+                Expression::add(Expression::meta_variable("a"), Expression::constant(0)),
+                Expression::meta_variable("a"),
+            ),
+            Rule::new(
+                "Commutativity of multiplication",
+                Expression::multiply(Expression::meta_variable("a"), Expression::meta_variable("b")),
+                Expression::multiply(Expression::meta_variable("b"), Expression::meta_variable("a")),
+            ),
+            Rule::new(
+                "Identity element of multiplication",
+                Expression::multiply(Expression::meta_variable("a"), Expression::constant(1)),
+                Expression::meta_variable("a"),
+            ),
+            Rule::new(
+                "Multiplication by zero",
+                Expression::multiply(Expression::meta_variable("a"), Expression::constant(0)),
+                Expression::constant(0),
+            ),
+            Rule::new(
+                "Associativity of addition",
+                Expression::add(
                     Expression::add(Expression::meta_variable("a"), Expression::meta_variable("b")),
                     Expression::meta_variable("c"),
                 ),
-                rhs: Expression::add(
+                Expression::add(
                     Expression::meta_variable("a"),
                     Expression::add(Expression::meta_variable("b"), Expression::meta_variable("c")),
                 ),
-            },
-            // Associativity of multiplication
-            Rule {
-                lhs: Expression::multiply(
+            ),
+            Rule::new(
+                "Associativity of multiplication",
+                Expression::multiply(
                     Expression::multiply(Expression::meta_variable("a"), Expression::meta_variable("b")),
                     Expression::meta_variable("c"),
                 ),
-                rhs: Expression::multiply(
+                Expression::multiply(
                     Expression::meta_variable("a"),
                     Expression::multiply(Expression::meta_variable("b"), Expression::meta_variable("c")),
                 ),
-            },
-            // Associativity of multiplication and division
-            Rule {
-                lhs: Expression::divide(
+            ),
+            Rule::new(
+                "Associativity of multiplication and division",
+                Expression::divide(
                     Expression::multiply(Expression::meta_variable("a"), Expression::meta_variable("b")),
                     Expression::meta_variable("c"),
                 ),
-                rhs: Expression::multiply(
+                Expression::multiply(
                     Expression::meta_variable("a"),
                     Expression::divide(Expression::meta_variable("b"), Expression::meta_variable("c")),
                 ),
-            },
-            // Associativity of multiplication and division2
-            Rule {
-                lhs: Expression::divide(
+            ),
+            Rule::new(
+                "Associativity of multiplication and division2",
+                Expression::divide(
                     Expression::meta_variable("c"),
                     Expression::multiply(Expression::meta_variable("a"), Expression::meta_variable("b")),
                 ),
-                rhs: Expression::divide(
+                Expression::divide(
                     Expression::divide(Expression::meta_variable("c"), Expression::meta_variable("a")),
                     Expression::meta_variable("b")
                 ),
-            },
-            // Distributivity of multiplication over addition (left)
-            Rule {
-                lhs: Expression::multiply(
+            ),
+            Rule::new(
+                "Distributivity of multiplication over addition (left)",
+                Expression::multiply(
                     Expression::meta_variable("a"),
                     Expression::add(Expression::meta_variable("b"), Expression::meta_variable("c")),
                 ),
-                rhs: Expression::add(
+                Expression::add(
                     Expression::multiply(Expression::meta_variable("a"), Expression::meta_variable("b")),
                     Expression::multiply(Expression::meta_variable("a"), Expression::meta_variable("c")),
                 ),
-            },
-            // Distributivity of multiplication over addition (right)
-            Rule {
-                lhs: Expression::multiply(
+            ),
+            Rule::new(
+                "Distributivity of multiplication over addition (right)",
+                Expression::multiply(
                     Expression::add(Expression::meta_variable("b"), Expression::meta_variable("c")),
                     Expression::meta_variable("a"),
                 ),
-                rhs: Expression::add(
+                Expression::add(
                     Expression::multiply(Expression::meta_variable("b"), Expression::meta_variable("a")),
                     Expression::multiply(Expression::meta_variable("c"), Expression::meta_variable("a")),
                 ),
-            },
-            // Negation of zero
-            Rule {
-                lhs: Expression::negate(Expression::constant(0)),
-                rhs: Expression::constant(0),
-            },
-            // Multiplication by minus 1
-            Rule {
-                lhs: Expression::multiply(Expression::meta_variable("a"), Expression::constant(-1)),
-                rhs: Expression::negate(Expression::meta_variable("a")),
-            },
-            // Negation of a negation
-            Rule {
-                lhs: Expression::negate(Expression::negate(Expression::meta_variable("a"))),
-                rhs: Expression::meta_variable("a"),
-            },
-            // Cancelling negation in division
-            Rule {
-                lhs: Expression::divide(Expression::negate(Expression::meta_variable("a")), Expression::negate(Expression::meta_variable("b"))),
-                rhs: Expression::divide(Expression::meta_variable("a"), Expression::meta_variable("b")),
-            },
-            // Subtraction as addition with negation
-            Rule {
-                lhs: Expression::subtract(Expression::meta_variable("a"), Expression::meta_variable("b")),
-                rhs: Expression::add(Expression::meta_variable("a"), Expression::negate(Expression::meta_variable("b"))),
-            },
-            // Division by one
-            Rule {
-                lhs: Expression::divide(Expression::meta_variable("a"), Expression::constant(1)),
-                rhs: Expression::meta_variable("a"),
-            },
-            // Division by the same value
-            Rule {
-                lhs: Expression::divide(Expression::meta_variable("a"), Expression::meta_variable("a")),
-                rhs: Expression::constant(1),
-            },
-        ];
-    }
-
-}
\ No newline at end of file
+            ),
+            Rule::new(
+                "Negation of zero",
+                Expression::negate(Expression::constant(0)),
+                Expression::constant(0),
+            ),
+            Rule::new(
+                "Multiplication by minus 1",
+                Expression::multiply(Expression::meta_variable("a"), Expression::constant(-1)),
+                Expression::negate(Expression::meta_variable("a")),
+            ),
+            Rule::new(
+                "Negation of a negation",
+                Expression::negate(Expression::negate(Expression::meta_variable("a"))),
+                Expression::meta_variable("a"),
+            ),
+            Rule::new(
+                "Cancelling negation in division",
+                Expression::divide(Expression::negate(Expression::meta_variable("a")), Expression::negate(Expression::meta_variable("b"))),
+                Expression::divide(Expression::meta_variable("a"), Expression::meta_variable("b")),
+            ),
+            Rule::new(
+                "Subtraction as addition with negation",
+                Expression::subtract(Expression::meta_variable("a"), Expression::meta_variable("b")),
+                Expression::add(Expression::meta_variable("a"), Expression::negate(Expression::meta_variable("b"))),
+            ),
+            Rule::new(
+                "Division by one",
+                Expression::divide(Expression::meta_variable("a"), Expression::constant(1)),
+                Expression::meta_variable("a"),
+            ),
+            Rule::new(
+                "Division by the same value",
+                Expression::divide(Expression::meta_variable("a"), Expression::meta_variable("a")),
+                Expression::constant(1),
+            )
+            .with_condition(NonZero::new("a")),
+            Rule::new(
+                "Cancelling common constant factors in division",
+                Expression::divide(
+                    Expression::multiply(Expression::meta_variable("a"), Expression::meta_variable("c")),
+                    Expression::multiply(Expression::meta_variable("b"), Expression::meta_variable("d")),
+                ),
+                CancelCommonFactors,
+            ),
+        ]
+    }
+
+}
+
+/// Rewrites `(?a * ?c) / (?b * ?d)` for constants `?c` and `?d` by dividing
+/// both constants by their greatest common divisor.
+struct CancelCommonFactors;
+
+impl CancelCommonFactors {
+    /// The pattern to rewrite into for the constants `c` and `d`, if they
+    /// have a common factor to cancel.
+    fn pattern(c: i64, d: i64) -> Option<Expression> {
+        let divisor = i64::try_from(gcd(c.unsigned_abs(), d.unsigned_abs())).ok()?;
+        if c == 0 || d == 0 || divisor == 1 {
+            return None;
+        }
+
+        Some(Expression::divide(
+            Expression::multiply(Expression::meta_variable("a"), Expression::constant(c / divisor)),
+            Expression::multiply(Expression::meta_variable("b"), Expression::constant(d / divisor)),
+        ))
+    }
+}
+
+impl<N: Analysis<NodeType>> Applier<NodeType, N> for CancelCommonFactors {
+    fn apply(
+        &self,
+        egraph: &mut EGraph<NodeType, N>,
+        _id: Id,
+        subst: &Subst,
+    ) -> Result<Vec<Id>, EGraphError> {
+        let c = constant_value(egraph, subst, Symbol::new("c"));
+        let d = constant_value(egraph, subst, Symbol::new("d"));
+        match c.zip(d).and_then(|(c, d)| CancelCommonFactors::pattern(c, d)) {
+            Some(pattern) => Ok(vec![egraph.instantiate(&pattern, subst)?]),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn rebind(&self, egraph: &mut EGraph<NodeType, N>, subst: &mut Subst) {
+        bind_constant(egraph, subst, Symbol::new("c"));
+        bind_constant(egraph, subst, Symbol::new("d"));
+    }
+
+    fn apply_term(&self, assignment: &Assignment<NodeType>) -> Option<Vec<Expression>> {
+        let constant = |x| assignment.get(&Symbol::new(x)).and_then(Expression::const_eval);
+        let pattern = constant("c")
+            .zip(constant("d"))
+            .and_then(|(c, d)| CancelCommonFactors::pattern(c, d));
+        Some(pattern.map(|pattern| pattern.apply_assignment(assignment)).into_iter().collect())
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// The first step of a proof that does not hold, see `check_proof`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidStep {
+    /// The index of the step in the proof.
+    pub step: usize,
+    pub reason: InvalidReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidReason {
+    /// The position does not exist in the terms before and after the step.
+    Position,
+    /// The term changed outside of the rewritten position.
+    OutsidePosition,
+    /// No rule with this name was given.
+    UnknownRule(String),
+    /// The rule computes its right-hand side in a way that cannot be checked.
+    UncheckedApplier(String),
+    /// The left-hand side of the rule does not match the rewritten subterm.
+    NoMatch(String),
+    /// The rule rewrites the subterm into a different term.
+    WrongResult(String),
+    /// A condition of the rule does not hold for the rewritten subterm.
+    ConditionFails(String),
+    /// The rule has a condition that cannot be checked on terms.
+    UncheckedCondition(String),
+    /// The subterm does not evaluate to the constant it was replaced with.
+    WrongConstant,
+    /// The justification cannot be checked without trusting the graph.
+    Unchecked(Justification),
+}
+
+impl fmt::Display for InvalidStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "step {} is invalid: ", self.step)?;
+        match &self.reason {
+            InvalidReason::Position => write!(f, "the position does not exist"),
+            InvalidReason::OutsidePosition => {
+                write!(f, "the term changed outside of the position")
+            }
+            InvalidReason::UnknownRule(name) => write!(f, "there is no rule {}", name),
+            InvalidReason::UncheckedApplier(name) => {
+                write!(f, "the right-hand side of {} cannot be checked", name)
+            }
+            InvalidReason::NoMatch(name) => {
+                write!(f, "the left-hand side of {} does not match", name)
+            }
+            InvalidReason::WrongResult(name) => write!(f, "{} does not produce this term", name),
+            InvalidReason::ConditionFails(name) => {
+                write!(f, "a condition of {} does not hold", name)
+            }
+            InvalidReason::UncheckedCondition(name) => {
+                write!(f, "a condition of {} cannot be checked", name)
+            }
+            InvalidReason::WrongConstant => {
+                write!(f, "the subterm does not evaluate to this constant")
+            }
+            InvalidReason::Unchecked(justification) => {
+                write!(f, "{} cannot be checked", justification)
+            }
+        }
+    }
+}
+
+impl Error for InvalidStep {}
+
+/// Checks every step of a proof against `rules` without looking at any graph,
+/// returning the first step that does not hold. Rule steps are checked by
+/// matching the left-hand side against the rewritten subterm and comparing the
+/// instantiated right-hand side to the result. Conditions of rules are checked
+/// on the subterms bound to their variables.
+pub fn check_proof<N: Analysis<NodeType>>(
+    rules: &[Rule<NodeType, N>],
+    proof: &Explanation,
+) -> Result<(), InvalidStep> {
+    let mut before = &proof.start;
+    for (index, step) in proof.steps.iter().enumerate() {
+        check_step(rules, before, step).map_err(|reason| InvalidStep {
+            step: index,
+            reason,
+        })?;
+        before = &step.term;
+    }
+    Ok(())
+}
+
+/// Checks that `justification` rewrites `from` into `to` at the root, the
+/// same way `check_proof` checks a step.
+pub fn check_rewrite<N: Analysis<NodeType>>(
+    rules: &[Rule<NodeType, N>],
+    justification: &Justification,
+    from: &Expression,
+    to: &Expression,
+) -> Result<(), InvalidReason> {
+    let step = ProofStep {
+        justification: justification.clone(),
+        backward: false,
+        position: vec![],
+        term: to.clone(),
+    };
+    check_step(rules, from, &step)
+}
+
+fn check_step<N: Analysis<NodeType>>(
+    rules: &[Rule<NodeType, N>],
+    before: &Expression,
+    step: &ProofStep,
+) -> Result<(), InvalidReason> {
+    let (Some(old), Some(new)) = (
+        before.subterm(&step.position),
+        step.term.subterm(&step.position),
+    ) else {
+        return Err(InvalidReason::Position);
+    };
+
+    let mut expected = before.clone();
+    *expected
+        .subterm_mut(&step.position)
+        .expect("the position exists in the term before") = new.clone();
+    if expected != step.term {
+        return Err(InvalidReason::OutsidePosition);
+    }
+
+    let (from, to) = if step.backward { (new, old) } else { (old, new) };
+    match &step.justification {
+        Justification::Rule { name, .. } => {
+            let rule = rules
+                .iter()
+                .find(|rule| rule.name == *name)
+                .ok_or_else(|| InvalidReason::UnknownRule(name.clone()))?;
+            let assignment = rule
+                .lhs
+                .structural_match(from)
+                .ok_or_else(|| InvalidReason::NoMatch(name.clone()))?;
+            let results = rule
+                .rhs
+                .apply_term(&assignment)
+                .ok_or_else(|| InvalidReason::UncheckedApplier(name.clone()))?;
+            if !results.contains(to) {
+                return Err(InvalidReason::WrongResult(name.clone()));
+            }
+            for condition in rule.conditions.iter() {
+                match condition.check_term(&assignment) {
+                    Some(true) => {}
+                    Some(false) => return Err(InvalidReason::ConditionFails(name.clone())),
+                    None => return Err(InvalidReason::UncheckedCondition(name.clone())),
+                }
+            }
+            Ok(())
+        }
+        Justification::ConstantFold => {
+            let is_constant = matches!(to.t, NodeType::Const(_));
+            if !is_constant || from.const_eval().is_none() || from.const_eval() != to.const_eval() {
+                return Err(InvalidReason::WrongConstant);
+            }
+            Ok(())
+        }
+        justification => Err(InvalidReason::Unchecked(justification.clone())),
+    }
+}
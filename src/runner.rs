@@ -0,0 +1,301 @@
+use std::borrow::BorrowMut;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::analysis::Analysis;
+use crate::explain::Justification;
+use crate::extract::{AstSize, Extractor};
+use crate::graphs::*;
+use crate::rules::Rule;
+use crate::scheduler::{Scheduler, SimpleScheduler};
+
+/// Bounds on a saturation run. The run stops at the end of the first
+/// iteration that exceeds any of them.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    pub iterations: usize,
+    pub nodes: usize,
+    pub classes: usize,
+    pub time: Duration,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            iterations: 30,
+            nodes: 10_000,
+            classes: 10_000,
+            time: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    /// An iteration did not change the graph, so no rule can add anything.
+    Saturated,
+    IterationLimit(usize),
+    NodeLimit(usize),
+    ClassLimit(usize),
+    TimeLimit(Duration),
+    /// The goal set with `Runner::set_goal` holds.
+    GoalReached,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Saturated => write!(f, "saturated"),
+            StopReason::IterationLimit(n) => write!(f, "iteration limit of {} reached", n),
+            StopReason::NodeLimit(n) => write!(f, "node limit of {} reached", n),
+            StopReason::ClassLimit(n) => write!(f, "class limit of {} reached", n),
+            StopReason::TimeLimit(t) => write!(f, "time limit of {:?} reached", t),
+            StopReason::GoalReached => write!(f, "goal reached"),
+        }
+    }
+}
+
+/// Statistics of a single search, apply and rebuild round.
+#[derive(Debug, Clone)]
+pub struct Iteration {
+    pub number_of_nodes: usize,
+    pub number_of_classes: usize,
+    /// The number of matches applied for each rule, by rule name.
+    pub applied: HashMap<String, usize>,
+    pub search_time: Duration,
+    pub apply_time: Duration,
+    pub rebuild_time: Duration,
+}
+
+type Hook<L, N> = Box<dyn FnMut(&mut EGraph<L, N>) -> Result<(), EGraphError>>;
+type Goal<L, N> = Box<dyn Fn(&EGraph<L, N>) -> bool>;
+
+/// Runs equality saturation: every iteration searches all rules, applies
+/// the matches for which their conditions hold, and rebuilds the graph,
+/// until nothing changes or a limit is hit.
+///
+/// The runner owns its graph by default, but it can also work on a borrowed
+/// one, as in `Runner::new(&mut egraph, limits)`.
+pub struct Runner<L: Language = NodeType, N: Analysis<L> = (), G = EGraph<L, N>> {
+    pub egraph: G,
+    pub iterations: Vec<Iteration>,
+    pub stop_reason: Option<StopReason>,
+    limits: Limits,
+    hooks: Vec<Hook<L, N>>,
+    goal: Option<Goal<L, N>>,
+    scheduler: Box<dyn Scheduler<L, N>>,
+}
+
+impl<L: Language, N: Analysis<L>, G: BorrowMut<EGraph<L, N>>> Runner<L, N, G> {
+    pub fn new(egraph: G, limits: Limits) -> Runner<L, N, G> {
+        Runner {
+            egraph,
+            iterations: vec![],
+            stop_reason: None,
+            limits,
+            hooks: vec![],
+            goal: None,
+            scheduler: Box::new(SimpleScheduler),
+        }
+    }
+
+    /// Replaces the scheduler deciding which rules are searched in each
+    /// iteration. By default every rule is searched every time.
+    pub fn set_scheduler(&mut self, scheduler: impl Scheduler<L, N> + 'static) {
+        self.scheduler = Box::new(scheduler);
+    }
+
+    /// Adds a hook that may modify the graph at the start of every iteration,
+    /// before the rules are searched.
+    pub fn add_hook(
+        &mut self,
+        hook: impl FnMut(&mut EGraph<L, N>) -> Result<(), EGraphError> + 'static,
+    ) {
+        self.hooks.push(Box::new(hook));
+    }
+
+    /// Stops the run as soon as `goal` holds for the rebuilt graph, checked
+    /// before every iteration.
+    pub fn set_goal(&mut self, goal: impl Fn(&EGraph<L, N>) -> bool + 'static) {
+        self.goal = Some(Box::new(goal));
+    }
+
+    pub fn run(&mut self, rules: &[Rule<L, N>]) -> Result<StopReason, EGraphError> {
+        let start = Instant::now();
+        self.egraph.borrow_mut().rebuild();
+
+        let stop_reason = loop {
+            let egraph = self.egraph.borrow();
+            if self.goal.as_ref().is_some_and(|goal| goal(egraph)) {
+                break StopReason::GoalReached;
+            }
+            if let Some(stop_reason) = self.check_limits(start) {
+                break stop_reason;
+            }
+
+            let number_of_nodes = egraph.number_of_nodes();
+            let number_of_classes = egraph.number_of_classes();
+            let changed = self.run_one(rules)?;
+
+            let egraph = self.egraph.borrow();
+            if !changed
+                && egraph.number_of_nodes() == number_of_nodes
+                && egraph.number_of_classes() == number_of_classes
+                && self.scheduler.can_stop(self.iterations.len())
+            {
+                break StopReason::Saturated;
+            }
+        };
+
+        self.stop_reason = Some(stop_reason.clone());
+        Ok(stop_reason)
+    }
+
+    /// Runs a single iteration, returning whether any union merged classes.
+    fn run_one(&mut self, rules: &[Rule<L, N>]) -> Result<bool, EGraphError> {
+        let egraph = self.egraph.borrow_mut();
+        for hook in self.hooks.iter_mut() {
+            hook(egraph)?;
+        }
+        egraph.rebuild();
+
+        let iteration = self.iterations.len();
+        let search_start = Instant::now();
+        let matches: Vec<Vec<(Subst, Id)>> = rules
+            .iter()
+            .map(|rule| {
+                let matches = self.scheduler.search_rule(iteration, egraph, rule);
+                matches
+                    .into_iter()
+                    .filter(|(subst, id)| rule.check(egraph, *id, subst))
+                    .collect()
+            })
+            .collect();
+        let search_time = search_start.elapsed();
+
+        let apply_start = Instant::now();
+        let mut applied = HashMap::new();
+        let mut changed = false;
+        for (rule, matches) in rules.iter().zip(matches) {
+            for (subst, id) in matches.iter() {
+                // Explanations need the term the rule rewrote, not just its
+                // class, so the left-hand side is instantiated as well, with
+                // the nodes the rule relied on.
+                let mut subst = subst.clone();
+                let lhs_id = if egraph.are_explanations_enabled() {
+                    rule.rebind(egraph, &mut subst);
+                    egraph.instantiate(&rule.lhs, &subst)?
+                } else {
+                    *id
+                };
+                for new_id in rule.rhs.apply(egraph, *id, &subst)? {
+                    let justification = Justification::Rule {
+                        name: rule.name.clone(),
+                        subst: subst.clone(),
+                    };
+                    changed |= egraph.union_because(lhs_id, new_id, justification)?;
+                }
+            }
+            *applied.entry(rule.name.clone()).or_insert(0) += matches.len();
+        }
+        let apply_time = apply_start.elapsed();
+
+        let rebuild_start = Instant::now();
+        egraph.rebuild();
+        let rebuild_time = rebuild_start.elapsed();
+
+        self.iterations.push(Iteration {
+            number_of_nodes: egraph.number_of_nodes(),
+            number_of_classes: egraph.number_of_classes(),
+            applied,
+            search_time,
+            apply_time,
+            rebuild_time,
+        });
+
+        Ok(changed)
+    }
+
+    fn check_limits(&self, start: Instant) -> Option<StopReason> {
+        if self.iterations.len() >= self.limits.iterations {
+            return Some(StopReason::IterationLimit(self.limits.iterations));
+        }
+
+        if let Some(iteration) = self.iterations.last() {
+            if iteration.number_of_nodes > self.limits.nodes {
+                return Some(StopReason::NodeLimit(self.limits.nodes));
+            }
+            if iteration.number_of_classes > self.limits.classes {
+                return Some(StopReason::ClassLimit(self.limits.classes));
+            }
+        }
+
+        if start.elapsed() > self.limits.time {
+            return Some(StopReason::TimeLimit(self.limits.time));
+        }
+
+        None
+    }
+}
+
+/// The outcome of `EGraph::prove_equal`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Equivalence<L = NodeType> {
+    /// Both expressions ended up in the same class.
+    Proven,
+    /// A limit was hit before the expressions ended up in the same class.
+    Unknown(StopReason),
+    /// The graph saturated with the expressions in different classes, so the
+    /// rules cannot prove them equal. As a hint of where they differ, holds
+    /// the smallest term of each class.
+    Counterexample {
+        lhs: Expression<L>,
+        rhs: Expression<L>,
+    },
+}
+
+impl<L: Language> fmt::Display for Equivalence<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Equivalence::Proven => write!(f, "proven"),
+            Equivalence::Unknown(reason) => write!(f, "unknown ({})", reason),
+            Equivalence::Counterexample { lhs, rhs } => {
+                write!(f, "not provable, {} and {} stay apart", lhs, rhs)
+            }
+        }
+    }
+}
+
+impl<L: Language, N: Analysis<L>> EGraph<L, N> {
+    /// Adds both expressions and runs equality saturation until their
+    /// classes coincide, the graph saturates or a limit is hit.
+    pub fn prove_equal(
+        &mut self,
+        lhs: &Expression<L>,
+        rhs: &Expression<L>,
+        rules: &[Rule<L, N>],
+        limits: Limits,
+    ) -> Result<Equivalence<L>, EGraphError> {
+        let lhs_id = self.add_expression(lhs.clone());
+        let rhs_id = self.add_expression(rhs.clone());
+
+        let mut runner = Runner::new(&mut *self, limits);
+        runner.set_goal(move |egraph| egraph.find(lhs_id) == egraph.find(rhs_id));
+        match runner.run(rules)? {
+            _ if self.find(lhs_id) == self.find(rhs_id) => Ok(Equivalence::Proven),
+            StopReason::Saturated => {
+                let extractor = Extractor::new(self, AstSize);
+                let smallest = |id: Id, original: &Expression<L>| match extractor.find_best(id) {
+                    Ok((_, expression)) => expression,
+                    Err(_) => original.clone(),
+                };
+                Ok(Equivalence::Counterexample {
+                    lhs: smallest(lhs_id, lhs),
+                    rhs: smallest(rhs_id, rhs),
+                })
+            }
+            reason => Ok(Equivalence::Unknown(reason)),
+        }
+    }
+}
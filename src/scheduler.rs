@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use crate::analysis::Analysis;
+use crate::graphs::*;
+use crate::rules::Rule;
+
+/// Decides which rules the `Runner` searches in each iteration.
+pub trait Scheduler<L: Language, N: Analysis<L>> {
+    /// Searches `rule` in the given iteration. A scheduler may skip the search
+    /// and return no matches.
+    fn search_rule(
+        &mut self,
+        iteration: usize,
+        egraph: &EGraph<L, N>,
+        rule: &Rule<L, N>,
+    ) -> Vec<(Subst, Id)>;
+
+    /// Whether the runner may stop because an iteration changed nothing. A
+    /// scheduler that skipped rules should return `false` so they get another
+    /// chance.
+    fn can_stop(&mut self, _iteration: usize) -> bool {
+        true
+    }
+}
+
+/// Searches every rule in every iteration.
+#[derive(Debug, Clone, Default)]
+pub struct SimpleScheduler;
+
+impl<L: Language, N: Analysis<L>> Scheduler<L, N> for SimpleScheduler {
+    fn search_rule(
+        &mut self,
+        _iteration: usize,
+        egraph: &EGraph<L, N>,
+        rule: &Rule<L, N>,
+    ) -> Vec<(Subst, Id)> {
+        rule.search(egraph)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct RuleStats {
+    times_banned: usize,
+    banned_until: usize,
+}
+
+/// Bans a rule for a number of iterations once it matches too often, so that
+/// explosive rules such as commutativity and associativity cannot starve the
+/// others. Both the match threshold and the ban length double every time the
+/// same rule is banned.
+#[derive(Debug, Clone)]
+pub struct BackoffScheduler {
+    match_limit: usize,
+    ban_length: usize,
+    stats: HashMap<String, RuleStats>,
+}
+
+impl BackoffScheduler {
+    pub fn new(match_limit: usize, ban_length: usize) -> BackoffScheduler {
+        BackoffScheduler {
+            match_limit,
+            ban_length,
+            stats: HashMap::new(),
+        }
+    }
+}
+
+impl Default for BackoffScheduler {
+    fn default() -> Self {
+        BackoffScheduler::new(1_000, 5)
+    }
+}
+
+impl<L: Language, N: Analysis<L>> Scheduler<L, N> for BackoffScheduler {
+    fn search_rule(
+        &mut self,
+        iteration: usize,
+        egraph: &EGraph<L, N>,
+        rule: &Rule<L, N>,
+    ) -> Vec<(Subst, Id)> {
+        let stats = self.stats.entry(rule.name.clone()).or_default();
+        if iteration < stats.banned_until {
+            return vec![];
+        }
+
+        let matches = rule.search(egraph);
+        let threshold = self.match_limit << stats.times_banned;
+        if matches.len() > threshold {
+            stats.banned_until = iteration + (self.ban_length << stats.times_banned);
+            stats.times_banned += 1;
+            return vec![];
+        }
+
+        matches
+    }
+
+    fn can_stop(&mut self, iteration: usize) -> bool {
+        let mut banned = false;
+        for stats in self.stats.values_mut() {
+            if iteration < stats.banned_until {
+                stats.banned_until = iteration;
+                banned = true;
+            }
+        }
+        !banned
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+
+/// An interned string, used for variable and pattern variable names. Symbols
+/// are cheap to copy, compare and hash. Each distinct name is stored once in a
+/// global table for the lifetime of the program.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(u32);
+
+#[derive(Default)]
+struct Interner {
+    names: Vec<&'static str>,
+    indices: HashMap<&'static str, u32>,
+}
+
+fn interner() -> &'static Mutex<Interner> {
+    static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+    INTERNER.get_or_init(|| Mutex::new(Interner::default()))
+}
+
+impl Symbol {
+    /// Returns the symbol for `name`, interning it on first use.
+    pub fn new(name: &str) -> Symbol {
+        let mut interner = interner().lock().unwrap();
+        if let Some(&index) = interner.indices.get(name) {
+            return Symbol(index);
+        }
+
+        let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+        let index = interner.names.len() as u32;
+        interner.names.push(name);
+        interner.indices.insert(name, index);
+        Symbol(index)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        interner().lock().unwrap().names[self.0 as usize]
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Symbol::new(name)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
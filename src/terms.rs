@@ -0,0 +1,439 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+
+use crate::analysis::Analysis;
+use crate::graphs::*;
+
+/// The classes that represent at least one finite term.
+pub(crate) fn productive_classes<L: Language, N: Analysis<L>>(
+    egraph: &EGraph<L, N>,
+) -> HashSet<Id> {
+    let mut productive = HashSet::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (id, class) in egraph.classes() {
+            if productive.contains(&id) {
+                continue;
+            }
+            if class
+                .children
+                .iter()
+                .any(|node| is_productive(egraph, &productive, node))
+            {
+                productive.insert(id);
+                changed = true;
+            }
+        }
+    }
+    productive
+}
+
+/// Whether a node only has children with finite terms.
+pub(crate) fn is_productive<L: Language, N: Analysis<L>>(
+    egraph: &EGraph<L, N>,
+    productive: &HashSet<Id>,
+    node: &Node<L>,
+) -> bool {
+    node.children
+        .iter()
+        .all(|child| productive.contains(&egraph.find(*child)))
+}
+
+/// The size of the largest term of the class `id`, or `None` if it has
+/// infinitely many terms because a cycle of productive nodes is reachable
+/// from it. Unproductive classes have no terms and a largest size of zero.
+pub(crate) fn largest_term_size<L: Language, N: Analysis<L>>(
+    egraph: &EGraph<L, N>,
+    productive: &HashSet<Id>,
+    id: Id,
+) -> Option<usize> {
+    fn visit<L: Language, N: Analysis<L>>(
+        egraph: &EGraph<L, N>,
+        productive: &HashSet<Id>,
+        id: Id,
+        on_path: &mut HashSet<Id>,
+        sizes: &mut HashMap<Id, usize>,
+    ) -> Option<usize> {
+        let id = egraph.find(id);
+        if let Some(size) = sizes.get(&id) {
+            return Some(*size);
+        }
+        if !productive.contains(&id) {
+            return Some(0);
+        }
+        if !on_path.insert(id) {
+            return None;
+        }
+
+        let class = egraph
+            .get_class(id)
+            .expect("children of classes belong to the graph");
+        let mut largest = 0;
+        for node in class.children.iter() {
+            if !is_productive(egraph, productive, node) {
+                continue;
+            }
+            let mut size = 1;
+            for child in node.children.iter() {
+                size += visit(egraph, productive, *child, on_path, sizes)?;
+            }
+            largest = largest.max(size);
+        }
+
+        on_path.remove(&id);
+        sizes.insert(id, largest);
+        Some(largest)
+    }
+
+    visit(
+        egraph,
+        productive,
+        id,
+        &mut HashSet::new(),
+        &mut HashMap::new(),
+    )
+}
+
+/// Lazily enumerates the distinct terms of a class, smallest first. Terms of
+/// each size are only computed once the smaller ones have been consumed, so
+/// `take(n)` is cheap even if the class has infinitely many terms.
+pub struct Terms<'a, L: Language, N: Analysis<L>> {
+    egraph: &'a EGraph<L, N>,
+    root: Id,
+    /// The size of the terms currently being yielded.
+    size: usize,
+    /// The largest term size, or `None` if there is no largest term.
+    largest: Option<usize>,
+    current: Rc<Vec<Expression<L>>>,
+    position: usize,
+    memo: HashMap<(Id, usize), Rc<Vec<Expression<L>>>>,
+}
+
+impl<'a, L: Language, N: Analysis<L>> Terms<'a, L, N> {
+    pub(crate) fn new(egraph: &'a EGraph<L, N>, root: Id) -> Terms<'a, L, N> {
+        let productive = productive_classes(egraph);
+        Terms {
+            egraph,
+            root: egraph.find(root),
+            size: 0,
+            largest: largest_term_size(egraph, &productive, root),
+            current: Rc::new(vec![]),
+            position: 0,
+            memo: HashMap::new(),
+        }
+    }
+
+    /// The terms of the class `id` with exactly `size` nodes.
+    fn terms_of_size(&mut self, id: Id, size: usize) -> Rc<Vec<Expression<L>>> {
+        let id = self.egraph.find(id);
+        if let Some(terms) = self.memo.get(&(id, size)) {
+            return terms.clone();
+        }
+
+        let class = self
+            .egraph
+            .get_class(id)
+            .expect("children of classes belong to the graph");
+        let mut seen = HashSet::new();
+        let mut terms = vec![];
+        for node in class.children.iter() {
+            if size <= node.children.len() {
+                continue;
+            }
+            for children in self.combinations(&node.children, size - 1) {
+                let term = Expression {
+                    t: node.t.clone(),
+                    children,
+                };
+                if seen.insert(term.clone()) {
+                    terms.push(term);
+                }
+            }
+        }
+
+        let terms = Rc::new(terms);
+        self.memo.insert((id, size), terms.clone());
+        terms
+    }
+
+    /// Every way of picking a term for each of the classes `ids` such that
+    /// the sizes add up to `size`.
+    fn combinations(&mut self, ids: &[Id], size: usize) -> Vec<Vec<Expression<L>>> {
+        let Some((first, rest)) = ids.split_first() else {
+            return if size == 0 { vec![vec![]] } else { vec![] };
+        };
+
+        let mut combinations = vec![];
+        for first_size in 1..=size.saturating_sub(rest.len()) {
+            let first_terms = self.terms_of_size(*first, first_size);
+            if first_terms.is_empty() {
+                continue;
+            }
+            let rest_combinations = self.combinations(rest, size - first_size);
+            for first_term in first_terms.iter() {
+                for rest_terms in rest_combinations.iter() {
+                    let mut children = vec![first_term.clone()];
+                    children.extend(rest_terms.iter().cloned());
+                    combinations.push(children);
+                }
+            }
+        }
+        combinations
+    }
+}
+
+impl<L: Language, N: Analysis<L>> Iterator for Terms<'_, L, N> {
+    type Item = Expression<L>;
+
+    fn next(&mut self) -> Option<Expression<L>> {
+        while self.position >= self.current.len() {
+            self.size += 1;
+            if self.largest.is_some_and(|largest| self.size > largest) {
+                return None;
+            }
+            self.current = self.terms_of_size(self.root, self.size);
+            self.position = 0;
+        }
+
+        self.position += 1;
+        Some(self.current[self.position - 1].clone())
+    }
+}
+
+/// The number of terms of a class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TermCount {
+    /// Finitely many terms. Counts saturate at `u128::MAX`.
+    Finite(u128),
+    /// A cycle gives the class infinitely many terms.
+    Infinite,
+}
+
+impl fmt::Display for TermCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TermCount::Finite(u128::MAX) => write!(f, "at least {}", u128::MAX),
+            TermCount::Finite(count) => write!(f, "{}", count),
+            TermCount::Infinite => write!(f, "infinitely many"),
+        }
+    }
+}
+
+/// The number of terms of every class for every size up to a bound, computed
+/// by size without building any of the terms. All counts saturate.
+pub(crate) struct TermCounts {
+    /// The number of terms of each class, indexed by size.
+    classes: HashMap<Id, Vec<u128>>,
+    /// For every node of every class, in the order of the nodes of the class,
+    /// the number of ways to pick terms for its first `j` children with a
+    /// total size of `t`, at `[j][t]`.
+    nodes: HashMap<Id, Vec<Vec<Vec<u128>>>>,
+}
+
+impl TermCounts {
+    pub(crate) fn new<L: Language, N: Analysis<L>>(
+        egraph: &EGraph<L, N>,
+        max_size: usize,
+    ) -> TermCounts {
+        let mut classes = HashMap::new();
+        let mut nodes = HashMap::new();
+        for (id, class) in egraph.classes() {
+            classes.insert(id, vec![0]);
+            let prefixes = class
+                .children
+                .iter()
+                .map(|node| {
+                    let mut prefix = vec![vec![1]];
+                    prefix.resize(node.children.len() + 1, vec![0]);
+                    prefix
+                })
+                .collect();
+            nodes.insert(id, prefixes);
+        }
+
+        let mut counts = TermCounts { classes, nodes };
+        for size in 1..=max_size {
+            counts.extend(egraph, size);
+        }
+        counts
+    }
+
+    /// Computes the counts of terms with `size` nodes, given all smaller ones.
+    fn extend<L: Language, N: Analysis<L>>(&mut self, egraph: &EGraph<L, N>, size: usize) {
+        // The children of a node of `size` nodes have `size - 1` nodes in
+        // total, and every child has at least one node. The ways to pick
+        // children of total size zero are known from the start.
+        let t = size - 1;
+        if t > 0 {
+            for (id, class) in egraph.classes() {
+                let prefixes = self.nodes.get_mut(&id).expect("every class is counted");
+                for (node, prefix) in class.children.iter().zip(prefixes.iter_mut()) {
+                    prefix[0].push(0);
+                    for (j, child) in node.children.iter().enumerate() {
+                        let child_counts = &self.classes[&egraph.find(*child)];
+                        let mut ways: u128 = 0;
+                        for a in 1..=t {
+                            ways = ways
+                                .saturating_add(prefix[j][t - a].saturating_mul(child_counts[a]));
+                        }
+                        prefix[j + 1].push(ways);
+                    }
+                }
+            }
+        }
+
+        for (id, _) in egraph.classes() {
+            let count = self.nodes[&id].iter().fold(0u128, |sum, prefix| {
+                sum.saturating_add(prefix[prefix.len() - 1][t])
+            });
+            self.classes
+                .get_mut(&id)
+                .expect("every class is counted")
+                .push(count);
+        }
+    }
+
+    /// The number of terms of the class `id` with at most `max_size` nodes.
+    pub(crate) fn up_to(&self, id: Id, max_size: usize) -> u128 {
+        self.classes[&id]
+            .iter()
+            .take(max_size + 1)
+            .fold(0, |sum, count| sum.saturating_add(*count))
+    }
+}
+
+/// A small SplitMix64 generator, enough for reproducible sampling.
+struct Random {
+    state: u64,
+}
+
+impl Random {
+    fn new(seed: u64) -> Random {
+        Random { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A uniformly random number below `bound`, which must not be zero.
+    fn below(&mut self, bound: u128) -> u128 {
+        // Rejecting the top of the range avoids favouring small numbers.
+        let limit = u128::MAX - u128::MAX % bound;
+        loop {
+            let x = (self.next_u64() as u128) << 64 | self.next_u64() as u128;
+            if x < limit {
+                return x % bound;
+            }
+        }
+    }
+
+    /// Picks an index with probability proportional to its weight. If the
+    /// weights saturated and do not add up to `total`, the last index with a
+    /// nonzero weight absorbs the difference.
+    fn choose(&mut self, total: u128, weights: impl Iterator<Item = u128>) -> usize {
+        let mut r = self.below(total);
+        let mut last = 0;
+        for (i, weight) in weights.enumerate() {
+            if r < weight {
+                return i;
+            }
+            r -= weight;
+            if weight > 0 {
+                last = i;
+            }
+        }
+        last
+    }
+}
+
+/// Draws terms of a fixed size from a class, uniformly at random among all of
+/// its distinct terms of that size. The same seed gives the same terms. If
+/// the class has no terms of that size the iterator is empty, otherwise it
+/// never ends. Sampling is only exactly uniform while the number of terms
+/// fits in a `u128`.
+pub struct Samples<'a, L: Language, N: Analysis<L>> {
+    egraph: &'a EGraph<L, N>,
+    root: Id,
+    size: usize,
+    counts: TermCounts,
+    random: Random,
+}
+
+impl<'a, L: Language, N: Analysis<L>> Samples<'a, L, N> {
+    pub(crate) fn new(
+        egraph: &'a EGraph<L, N>,
+        root: Id,
+        size: usize,
+        seed: u64,
+    ) -> Samples<'a, L, N> {
+        Samples {
+            egraph,
+            root: egraph.find(root),
+            size,
+            counts: TermCounts::new(egraph, size),
+            random: Random::new(seed),
+        }
+    }
+
+    /// Draws a term of the class `id` with `size` nodes, which must exist.
+    fn sample(&mut self, id: Id, size: usize) -> Expression<L> {
+        let id = self.egraph.find(id);
+        let class = self
+            .egraph
+            .get_class(id)
+            .expect("children of classes belong to the graph");
+        let prefixes = &self.counts.nodes[&id];
+
+        // Pick a node in proportion to the number of its terms of this size.
+        let total = self.counts.classes[&id][size];
+        let chosen = self.random.choose(
+            total,
+            prefixes
+                .iter()
+                .map(|prefix| prefix[prefix.len() - 1][size - 1]),
+        );
+        let node = &class.children[chosen];
+        let prefix = &prefixes[chosen];
+
+        // Split the remaining size among the children from the last one back,
+        // in proportion to the number of ways to complete each split.
+        let mut sizes = vec![0; node.children.len()];
+        let mut t = size - 1;
+        for j in (0..node.children.len()).rev() {
+            let child_counts = &self.counts.classes[&self.egraph.find(node.children[j])];
+            let weights = (1..=t).map(|a| prefix[j][t - a].saturating_mul(child_counts[a]));
+            let a = 1 + self.random.choose(prefix[j + 1][t], weights);
+            sizes[j] = a;
+            t -= a;
+        }
+
+        let t = node.t.clone();
+        let children = node.children.clone();
+        Expression {
+            t,
+            children: children
+                .into_iter()
+                .zip(sizes)
+                .map(|(child, size)| self.sample(child, size))
+                .collect(),
+        }
+    }
+}
+
+impl<L: Language, N: Analysis<L>> Iterator for Samples<'_, L, N> {
+    type Item = Expression<L>;
+
+    fn next(&mut self) -> Option<Expression<L>> {
+        if self.size == 0 || self.counts.classes[&self.root][self.size] == 0 {
+            return None;
+        }
+        Some(self.sample(self.root, self.size))
+    }
+}
@@ -0,0 +1,167 @@
+use crate::graphs::Id;
+
+/// Disjoint sets of ids with path compression and union by size.
+#[derive(Debug, Clone, Default)]
+pub struct UnionFind {
+    parents: Vec<Id>,
+    sizes: Vec<usize>,
+    /// Whether path compression is turned off, see `set_compression`.
+    frozen: bool,
+}
+
+impl UnionFind {
+    pub fn init() -> UnionFind {
+        UnionFind::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// Adds a new singleton set and returns its id.
+    pub fn make_set(&mut self) -> Id {
+        let id = Id::from(self.parents.len());
+        self.parents.push(id);
+        self.sizes.push(1);
+        id
+    }
+
+    /// Returns the root of the set containing `id` without changing anything,
+    /// so that a shared union-find can be used from several threads.
+    pub fn find(&self, id: Id) -> Id {
+        let mut root = id;
+        while self.parent(root) != root {
+            root = self.parent(root);
+        }
+        root
+    }
+
+    /// Same as `find`, but also points every entry on the way directly at the
+    /// root, unless path compression is turned off.
+    pub fn find_mut(&mut self, id: Id) -> Id {
+        let root = self.find(id);
+        if !self.frozen {
+            let mut current = id;
+            while current != root {
+                current = std::mem::replace(&mut self.parents[usize::from(current)], root);
+            }
+        }
+        root
+    }
+
+    /// Merges the sets containing `id1` and `id2`, returning the root of the
+    /// merged set. The larger set's root is kept.
+    pub fn union(&mut self, id1: Id, id2: Id) -> Id {
+        let root1 = self.find_mut(id1);
+        let root2 = self.find_mut(id2);
+        if root1 == root2 {
+            return root1;
+        }
+
+        let (root, child) = if self.sizes[usize::from(root1)] >= self.sizes[usize::from(root2)] {
+            (root1, root2)
+        } else {
+            (root2, root1)
+        };
+
+        self.parents[usize::from(child)] = root;
+        self.sizes[usize::from(root)] += self.sizes[usize::from(child)];
+        root
+    }
+
+    /// Turns path compression on or off. Unions can only be undone while it is
+    /// off, since compression may point ids past the root being split off.
+    pub fn set_compression(&mut self, compress: bool) {
+        self.frozen = !compress;
+    }
+
+    /// Removes the most recently added set, which must still be a singleton.
+    pub fn remove_last(&mut self) {
+        debug_assert_eq!(self.sizes.last(), Some(&1));
+        self.parents.pop();
+        self.sizes.pop();
+    }
+
+    /// Undoes the `union` that made `root` the root of `child`. Every union
+    /// after it must have been undone already.
+    pub fn undo_union(&mut self, root: Id, child: Id) {
+        debug_assert_eq!(self.parent(child), root);
+        self.parents[usize::from(child)] = child;
+        self.sizes[usize::from(root)] -= self.sizes[usize::from(child)];
+    }
+
+    fn parent(&self, id: Id) -> Id {
+        self.parents[usize::from(id)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sets(len: usize) -> UnionFind {
+        let mut sets = UnionFind::init();
+        for _ in 0..len {
+            sets.make_set();
+        }
+        sets
+    }
+
+    #[test]
+    fn find_mut_compresses_paths() {
+        let mut sets = sets(4);
+        // Builds the chain 0 -> 1 -> 2 by hand, since union keeps it flat.
+        sets.parents[0] = Id::from(1);
+        sets.parents[1] = Id::from(2);
+        assert_eq!(sets.find(Id::from(0)), Id::from(2));
+        assert_eq!(sets.parent(Id::from(0)), Id::from(1));
+
+        assert_eq!(sets.find_mut(Id::from(0)), Id::from(2));
+        assert_eq!(sets.parent(Id::from(0)), Id::from(2));
+        assert_eq!(sets.parent(Id::from(1)), Id::from(2));
+    }
+
+    #[test]
+    fn union_keeps_the_larger_root() {
+        let mut sets = sets(4);
+        let root = sets.union(Id::from(1), Id::from(2));
+        assert_eq!(sets.union(Id::from(0), Id::from(1)), root);
+        assert_eq!(sets.union(Id::from(3), Id::from(0)), root);
+        assert!((0..4).all(|index| sets.find(Id::from(index)) == root));
+        assert_eq!(sets.sizes[usize::from(root)], 4);
+    }
+
+    #[test]
+    fn frozen_sets_are_not_compressed() {
+        let mut sets = sets(3);
+        sets.parents[0] = Id::from(1);
+        sets.parents[1] = Id::from(2);
+        sets.set_compression(false);
+        assert_eq!(sets.find_mut(Id::from(0)), Id::from(2));
+        assert_eq!(sets.parent(Id::from(0)), Id::from(1));
+    }
+
+    #[test]
+    fn undo_union_splits_the_last_union() {
+        let mut sets = sets(3);
+        sets.set_compression(false);
+        let root = sets.union(Id::from(0), Id::from(1));
+        assert_eq!(root, Id::from(0));
+        assert_eq!(sets.union(Id::from(2), root), root);
+
+        sets.undo_union(root, Id::from(2));
+        assert_eq!(sets.find(Id::from(2)), Id::from(2));
+        assert_eq!(sets.sizes[usize::from(root)], 2);
+
+        sets.undo_union(root, Id::from(1));
+        assert!((0..3).all(|index| sets.find(Id::from(index)) == Id::from(index)));
+        assert_eq!(sets.sizes, vec![1, 1, 1]);
+
+        sets.remove_last();
+        assert_eq!(sets.len(), 2);
+    }
+}
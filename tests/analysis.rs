@@ -0,0 +1,59 @@
+use egraphs::analysis::*;
+use egraphs::graphs::*;
+
+fn value(graph: &EGraph<NodeType, ConstantFolding>, id: Id) -> Option<i64> {
+    graph.get_class(id).unwrap().data
+}
+
+#[test]
+fn constants_are_folded_when_added() {
+    let mut graph: EGraph<NodeType, ConstantFolding> = EGraph::init();
+    let product = graph.add_expression(Expression::multiply(
+        Expression::constant(2),
+        Expression::constant(3),
+    ));
+    let sum = graph.add_expression(Expression::add(
+        Expression::variable("x"),
+        Expression::constant(1),
+    ));
+
+    assert_eq!(value(&graph, product), Some(6));
+    assert_eq!(value(&graph, sum), None);
+    let six = graph.add_expression(Expression::constant(6));
+    assert_eq!(graph.find(product), graph.find(six));
+}
+
+#[test]
+fn merged_classes_keep_the_known_value() {
+    let mut graph: EGraph<NodeType, ConstantFolding> = EGraph::init();
+    let x = graph.add_expression(Expression::variable("x"));
+    let four = graph.add_expression(Expression::constant(4));
+
+    graph.union(x, four).unwrap();
+    graph.rebuild();
+    assert_eq!(value(&graph, x), Some(4));
+}
+
+#[test]
+fn folding_spreads_to_parents_during_rebuild() {
+    let mut graph: EGraph<NodeType, ConstantFolding> = EGraph::init();
+    let sum = graph.add_expression(Expression::add(
+        Expression::variable("x"),
+        Expression::constant(1),
+    ));
+    let negated = graph.add_expression(Expression::negate(Expression::add(
+        Expression::variable("x"),
+        Expression::constant(1),
+    )));
+    let x = graph.add_expression(Expression::variable("x"));
+    let four = graph.add_expression(Expression::constant(4));
+    assert_eq!(value(&graph, negated), None);
+
+    graph.union(x, four).unwrap();
+    graph.rebuild();
+
+    assert_eq!(value(&graph, sum), Some(5));
+    assert_eq!(value(&graph, negated), Some(-5));
+    let minus_five = graph.add_expression(Expression::constant(-5));
+    assert_eq!(graph.find(negated), graph.find(minus_five));
+}
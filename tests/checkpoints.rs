@@ -0,0 +1,127 @@
+use egraphs::analysis::ConstantFolding;
+use egraphs::graphs::*;
+use egraphs::rules::*;
+use egraphs::runner::*;
+use egraphs::symbol::Symbol;
+
+type Graph = EGraph<NodeType, ConstantFolding>;
+
+/// Everything observable about a graph: the class of every id, every class
+/// with its nodes and data, and the class every node is found in.
+fn snapshot(graph: &Graph, ids: usize) -> String {
+    let finds: Vec<Id> = (0..ids).map(|id| graph.find(Id::from(id))).collect();
+    let classes: Vec<String> = graph
+        .classes()
+        .map(|(id, class)| format!("{} {:?}", id, class))
+        .collect();
+    let lookups: Vec<Option<Id>> = graph
+        .classes()
+        .flat_map(|(_, class)| class.children.iter())
+        .map(|node| graph.lookup(node).unwrap())
+        .collect();
+    format!("{:?}\n{:?}\n{:?}", finds, classes, lookups)
+}
+
+/// One more than the largest id of a class.
+fn ids(graph: &Graph) -> usize {
+    graph
+        .classes()
+        .map(|(id, _)| usize::from(id) + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+fn saturate(graph: Graph, iterations: usize) -> Graph {
+    let rules: Vec<Rule<NodeType, ConstantFolding>> = Rule::rules();
+    let limits = Limits {
+        iterations,
+        ..Limits::default()
+    };
+    let mut runner = Runner::new(graph, limits);
+    runner.run(&rules).unwrap();
+    runner.egraph
+}
+
+fn expression() -> Expression {
+    Expression::divide(
+        Expression::multiply(Expression::variable("x"), Expression::constant(-6)),
+        Expression::multiply(Expression::variable("x"), Expression::constant(-3)),
+    )
+}
+
+#[test]
+fn pop_restores_the_graph() {
+    for explanations in [false, true] {
+        let mut graph = Graph::init();
+        if explanations {
+            graph = graph.with_explanations();
+        }
+        let id = graph.add_expression(expression());
+        let mut graph = saturate(graph, 2);
+        let ids = ids(&graph);
+        let nodes = graph.number_of_nodes();
+        let classes = graph.number_of_classes();
+        let before = snapshot(&graph, ids);
+
+        graph.push();
+        let mut graph = saturate(graph, 2);
+        let y = graph.add_expression(Expression::variable("y"));
+        graph.union(y, id).unwrap();
+        graph.rebuild();
+        assert!(graph.number_of_nodes() > nodes);
+        let y_node = Node {
+            t: NodeType::Var(Symbol::new("y")),
+            children: vec![],
+        };
+        assert!(graph.lookup(&y_node).unwrap().is_some());
+
+        graph.pop().unwrap();
+        assert_eq!(graph.number_of_nodes(), nodes);
+        assert_eq!(graph.number_of_classes(), classes);
+        assert_eq!(snapshot(&graph, ids), before);
+        assert_eq!(graph.lookup(&y_node).unwrap(), None);
+        assert_eq!(graph.pop(), Err(EGraphError::NoCheckpoint));
+
+        // The graph keeps working after going back.
+        let mut graph = saturate(graph, 2);
+        if explanations {
+            let best = Expression::multiply(
+                Expression::divide(Expression::variable("x"), Expression::variable("x")),
+                Expression::constant(2),
+            );
+            let explanation = graph.explain_equivalence(&expression(), &best).unwrap();
+            assert_eq!(
+                check_proof(&Rule::<NodeType, ConstantFolding>::rules(), &explanation),
+                Ok(())
+            );
+        }
+    }
+}
+
+#[test]
+fn nested_checkpoints() {
+    let mut graph = Graph::init().with_explanations();
+    graph.add_expression(expression());
+    graph.rebuild();
+    let outer_ids = ids(&graph);
+    let outer = snapshot(&graph, outer_ids);
+
+    graph.push();
+    let mut graph = saturate(graph, 1);
+    let ids = ids(&graph);
+    let inner = snapshot(&graph, ids);
+
+    graph.push();
+    let mut graph = saturate(graph, 2);
+    // Makes the analysis find new constants above `x`.
+    let x = graph.add_expression(Expression::variable("x"));
+    let zero = graph.add_expression(Expression::constant(0));
+    graph.union(zero, x).unwrap();
+    graph.rebuild();
+
+    graph.pop().unwrap();
+    assert_eq!(snapshot(&graph, ids), inner);
+    graph.pop().unwrap();
+    assert_eq!(snapshot(&graph, outer_ids), outer);
+    assert_eq!(graph.pop(), Err(EGraphError::NoCheckpoint));
+}
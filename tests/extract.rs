@@ -0,0 +1,182 @@
+use egraphs::extract::*;
+use egraphs::graphs::*;
+
+fn x() -> Expression {
+    Expression::variable("x")
+}
+
+fn y() -> Expression {
+    Expression::variable("y")
+}
+
+/// A class holding `-(-(-x))` and `(x+y)+(x+y)`: the first is smaller, the
+/// second is shallower.
+fn two_terms() -> (EGraph, Id) {
+    let mut graph: EGraph = EGraph::init();
+    let deep = graph.add_expression(Expression::negate(Expression::negate(Expression::negate(
+        x(),
+    ))));
+    let wide = graph.add_expression(Expression::add(
+        Expression::add(x(), y()),
+        Expression::add(x(), y()),
+    ));
+    graph.union(deep, wide).unwrap();
+    graph.rebuild();
+    (graph, deep)
+}
+
+#[test]
+fn ast_size_picks_the_smallest_term() {
+    let (graph, id) = two_terms();
+    let extractor = Extractor::new(&graph, AstSize);
+    let (cost, expression) = extractor.find_best(id).unwrap();
+    assert_eq!(cost, 4);
+    assert_eq!(
+        expression,
+        Expression::negate(Expression::negate(Expression::negate(x())))
+    );
+}
+
+#[test]
+fn ast_depth_picks_the_shallowest_term() {
+    let (graph, id) = two_terms();
+    let extractor = Extractor::new(&graph, AstDepth);
+    let (cost, expression) = extractor.find_best(id).unwrap();
+    assert_eq!(cost, 3);
+    assert_eq!(
+        expression,
+        Expression::add(Expression::add(x(), y()), Expression::add(x(), y()))
+    );
+}
+
+#[test]
+fn cyclic_classes_are_extracted() {
+    let mut graph: EGraph = EGraph::init();
+    let x_id = graph.add_expression(x());
+    let negated = graph.add_expression(Expression::negate(Expression::negate(x())));
+    let negated_once = graph.add_expression(Expression::negate(x()));
+    graph.union(x_id, negated).unwrap();
+    graph.rebuild();
+
+    let extractor = Extractor::new(&graph, AstSize);
+    assert_eq!(extractor.find_best(negated).unwrap(), (1, x()));
+    assert_eq!(
+        extractor.find_best(negated_once).unwrap(),
+        (2, Expression::negate(x()))
+    );
+}
+
+/// A class holding `(x*2)+(y*3)`, the smallest tree, and `a+a` with
+/// `a = -(-(-x))`, which is smaller once `a` is shared.
+fn shared_terms() -> (EGraph, Id) {
+    let mut graph: EGraph = EGraph::init();
+    let products = graph.add_expression(Expression::add(
+        Expression::multiply(x(), Expression::constant(2)),
+        Expression::multiply(y(), Expression::constant(3)),
+    ));
+    let negations = Expression::negate(Expression::negate(Expression::negate(x())));
+    let sum = graph.add_expression(Expression::add(negations.clone(), negations));
+    graph.union(products, sum).unwrap();
+    graph.rebuild();
+    (graph, sum)
+}
+
+#[test]
+fn sharing_beats_the_best_tree() {
+    let (graph, id) = shared_terms();
+    assert_eq!(Extractor::new(&graph, AstSize).find_best_cost(id), Some(7));
+
+    let extraction = DagExtractor::new(&graph, 10_000)
+        .extract(id, AstSize)
+        .unwrap();
+    assert!(extraction.optimal);
+    assert_eq!(extraction.cost, 5);
+    let negations = Expression::negate(Expression::negate(Expression::negate(x())));
+    assert_eq!(
+        extraction.expression.to_expression(),
+        Expression::add(negations.clone(), negations)
+    );
+    assert_eq!(
+        extraction.expression.to_string(),
+        "let v3 = -(-(-(x))) in (v3 + v3)"
+    );
+}
+
+#[test]
+fn exhausted_searches_fall_back_to_the_best_tree() {
+    let (graph, id) = shared_terms();
+    let extraction = DagExtractor::new(&graph, 0).extract(id, AstSize).unwrap();
+    assert!(!extraction.optimal);
+    assert_eq!(extraction.cost, 7);
+    assert_eq!(
+        extraction.expression.to_expression(),
+        Expression::add(
+            Expression::multiply(x(), Expression::constant(2)),
+            Expression::multiply(y(), Expression::constant(3)),
+        )
+    );
+}
+
+#[test]
+fn shared_expressions_bind_repeated_subterms() {
+    let node = |t, children| SharedNode { t, children };
+    let expression = SharedExpression {
+        nodes: vec![
+            node(NodeType::Const(2), vec![]),
+            node(NodeType::Mul, vec![0, 0]),
+            node(NodeType::Neg, vec![1]),
+            node(NodeType::Add, vec![1, 2]),
+        ],
+    };
+
+    // Leaves are repeated, only `2 * 2` gets a binding.
+    assert_eq!(expression.to_string(), "let v1 = (2 * 2) in (v1 + -(v1))");
+    let product = Expression::multiply(Expression::constant(2), Expression::constant(2));
+    assert_eq!(
+        expression.to_expression(),
+        Expression::add(product.clone(), Expression::negate(product))
+    );
+}
+
+#[test]
+fn top_k_returns_the_cheapest_terms() {
+    let (graph, id) = two_terms();
+    let terms = graph.top_k(id, 3, AstSize).unwrap();
+    assert_eq!(
+        terms,
+        vec![
+            (
+                4,
+                Expression::negate(Expression::negate(Expression::negate(x())))
+            ),
+            (
+                7,
+                Expression::add(Expression::add(x(), y()), Expression::add(x(), y()))
+            ),
+        ]
+    );
+}
+
+#[test]
+fn top_k_unfolds_cycles() {
+    let mut graph: EGraph = EGraph::init();
+    let x_id = graph.add_expression(x());
+    let negated = graph.add_expression(Expression::negate(Expression::negate(x())));
+    graph.union(x_id, negated).unwrap();
+    graph.rebuild();
+
+    let terms: Vec<(usize, String)> = graph
+        .top_k(x_id, 3, AstSize)
+        .unwrap()
+        .into_iter()
+        .map(|(cost, term)| (cost, term.to_string()))
+        .collect();
+    assert_eq!(
+        terms,
+        vec![
+            (1, "x".to_string()),
+            (3, "-(-(x))".to_string()),
+            (5, "-(-(-(-(x))))".to_string()),
+        ]
+    );
+}
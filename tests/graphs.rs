@@ -0,0 +1,49 @@
+use egraphs::graphs::*;
+
+#[test]
+fn equal_expressions_share_classes() {
+    let mut graph: EGraph = EGraph::init();
+    let expression = Expression::add(Expression::variable("x"), Expression::constant(1));
+    let id = graph.add_expression(expression.clone());
+    let classes = graph.classes().count();
+    assert_eq!(graph.add_expression(expression), id);
+    assert_eq!(graph.classes().count(), classes);
+}
+
+#[test]
+fn rebuild_restores_congruence() {
+    let mut graph: EGraph = EGraph::init();
+    let x = graph.add_expression(Expression::variable("x"));
+    let y = graph.add_expression(Expression::variable("y"));
+    let negated_x = graph.add_expression(Expression::negate(Expression::variable("x")));
+    let negated_y = graph.add_expression(Expression::negate(Expression::variable("y")));
+    assert_ne!(graph.find(negated_x), graph.find(negated_y));
+
+    graph.union(x, y).unwrap();
+    graph.rebuild();
+    assert_eq!(graph.find(negated_x), graph.find(negated_y));
+}
+
+#[test]
+fn graphs_can_be_shared_between_threads() {
+    fn assert_sync<T: Sync>() {}
+    assert_sync::<EGraph>();
+}
+
+#[test]
+fn unknown_ids_are_errors() {
+    let mut graph: EGraph = EGraph::init();
+    let node = Node {
+        t: NodeType::Neg,
+        children: vec![Id::from(99)],
+    };
+    assert!(matches!(
+        graph.lookup(&node),
+        Err(EGraphError::UnknownClass { .. })
+    ));
+    assert!(matches!(
+        graph.try_add(node),
+        Err(EGraphError::UnknownClass { .. })
+    ));
+    assert!(graph.get_class(Id::from(0)).is_err());
+}
@@ -0,0 +1,177 @@
+use egraphs::analysis::ConstantFolding;
+use egraphs::explain::{Explanation, Justification, ProofStep};
+use egraphs::graphs::*;
+use egraphs::rules::*;
+use egraphs::runner::*;
+
+/// Saturates a graph with explanations for `start` and explains why it
+/// equals `end`.
+fn explain(start: &Expression, end: &Expression) -> Explanation {
+    let rules: Vec<Rule> = Rule::rules();
+    let mut graph: EGraph = EGraph::init().with_explanations();
+    graph.add_expression(start.clone());
+    let limits = Limits {
+        iterations: 6,
+        nodes: 2_000,
+        ..Limits::default()
+    };
+    let mut runner = Runner::new(graph, limits);
+    runner.run(&rules).unwrap();
+    runner.egraph.explain_equivalence(start, end).unwrap()
+}
+
+#[test]
+fn explanations_show_why_conditions_hold() {
+    let three = Expression::add(
+        Expression::multiply(Expression::variable("x"), Expression::constant(0)),
+        Expression::constant(3),
+    );
+    let start = Expression::divide(three.clone(), three);
+    let explanation = explain(&start, &Expression::constant(1));
+
+    let [.., before, last] = &explanation.steps[..] else {
+        panic!("the explanation has several steps: {}", explanation);
+    };
+    assert!(matches!(
+        &last.justification,
+        Justification::Rule { name, .. } if name == "Division by the same value"
+    ));
+    assert_eq!(
+        before.term,
+        Expression::divide(Expression::constant(3), Expression::constant(3))
+    );
+}
+
+fn rewrite(start: Expression, rule: &str, term: Expression) -> Explanation {
+    Explanation {
+        start,
+        steps: vec![ProofStep {
+            justification: Justification::Rule {
+                name: rule.to_string(),
+                subst: Subst::new(),
+            },
+            backward: false,
+            position: vec![],
+            term,
+        }],
+    }
+}
+
+#[test]
+fn conditions_are_checked() {
+    let rules: Vec<Rule> = Rule::rules();
+    let zero = Expression::divide(Expression::constant(0), Expression::constant(0));
+    let proof = rewrite(zero, "Division by the same value", Expression::constant(1));
+    let invalid = check_proof(&rules, &proof).unwrap_err();
+    assert_eq!(
+        invalid.reason,
+        InvalidReason::ConditionFails("Division by the same value".to_string())
+    );
+
+    let three = Expression::divide(Expression::constant(3), Expression::constant(3));
+    let proof = rewrite(three, "Division by the same value", Expression::constant(1));
+    assert_eq!(check_proof(&rules, &proof), Ok(()));
+}
+
+#[test]
+fn computed_right_hand_sides_are_checked() {
+    let rules: Vec<Rule> = Rule::rules();
+    let name = "Cancelling common constant factors in division";
+    let x = || Expression::variable("x");
+    let fraction = |c, d| {
+        Expression::divide(
+            Expression::multiply(x(), Expression::constant(c)),
+            Expression::multiply(x(), Expression::constant(d)),
+        )
+    };
+
+    let proof = rewrite(fraction(-6, -3), name, fraction(-2, -1));
+    assert_eq!(check_proof(&rules, &proof), Ok(()));
+
+    let proof = rewrite(fraction(-6, -3), name, fraction(2, 1));
+    let invalid = check_proof(&rules, &proof).unwrap_err();
+    assert_eq!(invalid.reason, InvalidReason::WrongResult(name.to_string()));
+}
+
+#[test]
+fn explanations_check_when_conditions_use_constants() {
+    let rules: Vec<Rule> = Rule::rules();
+    let three = Expression::add(
+        Expression::multiply(Expression::variable("x"), Expression::constant(0)),
+        Expression::constant(3),
+    );
+    let start = Expression::divide(three.clone(), three);
+    let explanation = explain(&start, &Expression::constant(1));
+    assert_eq!(check_proof(&rules, &explanation), Ok(()), "{}", explanation);
+}
+
+#[test]
+fn explanations_check_when_appliers_use_constants() {
+    let rules: Vec<Rule> = Rule::rules();
+    let six = Expression::add(
+        Expression::multiply(Expression::variable("y"), Expression::constant(0)),
+        Expression::constant(6),
+    );
+    let fraction = |c, d| {
+        Expression::divide(
+            Expression::multiply(Expression::variable("x"), c),
+            Expression::multiply(Expression::variable("z"), d),
+        )
+    };
+    let start = fraction(six, Expression::constant(3));
+    let end = fraction(Expression::constant(2), Expression::constant(1));
+    let explanation = explain(&start, &end);
+    assert!(explanation.steps.len() > 1, "{}", explanation);
+    assert_eq!(check_proof(&rules, &explanation), Ok(()), "{}", explanation);
+}
+
+/// Saturates a graph for `start` and checks the raw and the minimized
+/// explanation from `start` to each of its smallest equivalent terms.
+fn check_explanations(start: Expression) {
+    let rules: Vec<Rule<NodeType, ConstantFolding>> = Rule::rules();
+    let mut graph: EGraph<NodeType, ConstantFolding> = EGraph::init().with_explanations();
+    let id = graph.add_expression(start.clone());
+    let limits = Limits {
+        iterations: 4,
+        ..Limits::default()
+    };
+    let mut runner = Runner::new(graph, limits);
+    runner.run(&rules).unwrap();
+    let mut graph = runner.egraph;
+
+    let terms: Vec<Expression> = graph.extract_all(id).unwrap().take(60).collect();
+    let holds = |justification: &_, from: &_, to: &_| {
+        check_rewrite(&rules, justification, from, to).is_ok()
+    };
+    for term in terms {
+        let explanation = graph.explain_equivalence(&start, &term).unwrap();
+        assert_eq!(explanation.end(), &term);
+        assert_eq!(check_proof(&rules, &explanation), Ok(()), "{}", explanation);
+
+        let minimized = graph.explain_minimized(&start, &term, holds).unwrap();
+        assert_eq!(minimized.explanation.end(), &term);
+        assert!(minimized.explanation.len() <= explanation.len());
+        assert_eq!(
+            check_proof(&rules, &minimized.explanation),
+            Ok(()),
+            "{}",
+            minimized
+        );
+    }
+}
+
+#[test]
+fn explanations_check() {
+    let x = || Expression::variable("x");
+    check_explanations(Expression::divide(
+        Expression::multiply(x(), Expression::constant(-6)),
+        Expression::multiply(x(), Expression::constant(-3)),
+    ));
+    check_explanations(Expression::add(
+        Expression::multiply(
+            Expression::variable("y"),
+            Expression::add(Expression::constant(2), Expression::constant(3)),
+        ),
+        Expression::multiply(Expression::constant(0), Expression::variable("z")),
+    ));
+}
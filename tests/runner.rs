@@ -0,0 +1,191 @@
+use egraphs::graphs::*;
+use egraphs::rules::*;
+use egraphs::runner::*;
+use egraphs::symbol::Symbol;
+
+fn rules(names: &[&str]) -> Vec<Rule> {
+    Rule::rules()
+        .into_iter()
+        .filter(|rule| names.contains(&rule.name.as_str()))
+        .collect()
+}
+
+fn sum() -> (EGraph, Vec<Rule>) {
+    let rules = rules(&["Commutativity of addition", "Associativity of addition"]);
+    let mut graph: EGraph = EGraph::init();
+    graph.add_expression(Expression::add(
+        Expression::add(Expression::variable("a"), Expression::variable("b")),
+        Expression::variable("c"),
+    ));
+    (graph, rules)
+}
+
+#[test]
+fn saturation_stops_when_nothing_changes() {
+    let rules = rules(&["Identity element of multiplication"]);
+    let mut graph: EGraph = EGraph::init();
+    let product = graph.add_expression(Expression::multiply(
+        Expression::variable("x"),
+        Expression::constant(1),
+    ));
+    let x = graph.add_expression(Expression::variable("x"));
+
+    let mut runner = Runner::new(graph, Limits::default());
+    assert_eq!(runner.run(&rules).unwrap(), StopReason::Saturated);
+    assert_eq!(runner.stop_reason, Some(StopReason::Saturated));
+    assert_eq!(runner.egraph.find(product), runner.egraph.find(x));
+
+    assert_eq!(runner.iterations.len(), 2);
+    for iteration in &runner.iterations {
+        assert_eq!(iteration.applied["Identity element of multiplication"], 1);
+    }
+}
+
+#[test]
+fn iteration_limit() {
+    let (graph, rules) = sum();
+    let limits = Limits {
+        iterations: 1,
+        ..Limits::default()
+    };
+    let mut runner = Runner::new(graph, limits);
+    assert_eq!(runner.run(&rules).unwrap(), StopReason::IterationLimit(1));
+
+    assert_eq!(runner.iterations.len(), 1);
+    let applied = &runner.iterations[0].applied;
+    assert_eq!(applied["Commutativity of addition"], 2);
+    assert_eq!(applied["Associativity of addition"], 1);
+}
+
+#[test]
+fn node_limit() {
+    let (graph, rules) = sum();
+    let nodes = graph.number_of_nodes();
+    let limits = Limits {
+        nodes,
+        ..Limits::default()
+    };
+    let mut runner = Runner::new(graph, limits);
+    assert_eq!(runner.run(&rules).unwrap(), StopReason::NodeLimit(nodes));
+    assert_eq!(runner.iterations.len(), 1);
+    assert!(runner.iterations[0].number_of_nodes > nodes);
+}
+
+#[test]
+fn class_limit() {
+    let (graph, rules) = sum();
+    let classes = graph.number_of_classes();
+    let limits = Limits {
+        classes,
+        ..Limits::default()
+    };
+    let mut runner = Runner::new(graph, limits);
+    assert_eq!(runner.run(&rules).unwrap(), StopReason::ClassLimit(classes));
+    assert_eq!(runner.iterations.len(), 1);
+    assert!(runner.iterations[0].number_of_classes > classes);
+}
+
+#[test]
+fn matches_whose_condition_fails_are_skipped() {
+    let rules = rules(&["Division by the same value"]);
+    let mut graph: EGraph = EGraph::init();
+    let quotient = |a: Expression| Expression::divide(a.clone(), a);
+    let zero = graph.add_expression(quotient(Expression::constant(0)));
+    let x = graph.add_expression(quotient(Expression::variable("x")));
+    let three = graph.add_expression(quotient(Expression::constant(3)));
+    let one = graph.add_expression(Expression::constant(1));
+
+    let mut runner = Runner::new(graph, Limits::default());
+    assert_eq!(runner.run(&rules).unwrap(), StopReason::Saturated);
+    let graph = &runner.egraph;
+    assert_eq!(graph.find(three), graph.find(one));
+    assert_ne!(graph.find(zero), graph.find(one));
+    assert_ne!(graph.find(x), graph.find(one));
+    assert_eq!(
+        runner.iterations[0].applied["Division by the same value"],
+        1
+    );
+}
+
+#[test]
+fn closures_compute_right_hand_sides() {
+    let doubling = Rule::new(
+        "Doubling",
+        Expression::add(
+            Expression::meta_variable("a"),
+            Expression::meta_variable("a"),
+        ),
+        |graph: &mut EGraph, _id: Id, subst: &Subst| {
+            let a = subst[&Symbol::new("a")];
+            let two = graph.add_expression(Expression::constant(2));
+            Ok(vec![graph.add(Node {
+                t: NodeType::Mul,
+                children: vec![two, a],
+            })])
+        },
+    );
+    let mut graph: EGraph = EGraph::init();
+    let sum = graph.add_expression(Expression::add(
+        Expression::variable("x"),
+        Expression::variable("x"),
+    ));
+    let other = graph.add_expression(Expression::add(
+        Expression::variable("x"),
+        Expression::variable("y"),
+    ));
+
+    let mut runner = Runner::new(graph, Limits::default());
+    assert_eq!(runner.run(&[doubling]).unwrap(), StopReason::Saturated);
+    let product = runner.egraph.add_expression(Expression::multiply(
+        Expression::constant(2),
+        Expression::variable("x"),
+    ));
+    assert_eq!(runner.egraph.find(sum), runner.egraph.find(product));
+    assert_eq!(runner.egraph.get_class(other).unwrap().children.len(), 1);
+}
+
+#[test]
+fn prove_equal_finds_proofs() {
+    let rules = rules(&["Identity element of addition", "Commutativity of addition"]);
+    let mut graph: EGraph = EGraph::init();
+    let lhs = Expression::add(Expression::constant(0), Expression::variable("x"));
+    let result = graph
+        .prove_equal(&lhs, &Expression::variable("x"), &rules, Limits::default())
+        .unwrap();
+    assert_eq!(result, Equivalence::Proven);
+}
+
+#[test]
+fn prove_equal_gives_up_at_limits() {
+    let (mut graph, rules) = sum();
+    let limits = Limits {
+        iterations: 2,
+        ..Limits::default()
+    };
+    let lhs = Expression::add(
+        Expression::add(Expression::variable("a"), Expression::variable("b")),
+        Expression::variable("c"),
+    );
+    let result = graph
+        .prove_equal(&lhs, &Expression::variable("d"), &rules, limits)
+        .unwrap();
+    assert_eq!(result, Equivalence::Unknown(StopReason::IterationLimit(2)));
+}
+
+#[test]
+fn prove_equal_reports_counterexamples() {
+    let rules = rules(&["Identity element of addition"]);
+    let mut graph: EGraph = EGraph::init();
+    let lhs = Expression::add(Expression::variable("x"), Expression::constant(0));
+    let rhs = Expression::multiply(Expression::variable("x"), Expression::constant(2));
+    let result = graph
+        .prove_equal(&lhs, &rhs, &rules, Limits::default())
+        .unwrap();
+    assert_eq!(
+        result,
+        Equivalence::Counterexample {
+            lhs: Expression::variable("x"),
+            rhs,
+        }
+    );
+}
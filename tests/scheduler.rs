@@ -0,0 +1,62 @@
+use egraphs::graphs::*;
+use egraphs::rules::*;
+use egraphs::runner::*;
+use egraphs::scheduler::*;
+
+fn identity() -> Rule {
+    Rule::new(
+        "Identity element of multiplication",
+        Expression::multiply(Expression::meta_variable("a"), Expression::constant(1)),
+        Expression::meta_variable("a"),
+    )
+}
+
+fn products(names: &[&str]) -> EGraph {
+    let mut graph: EGraph = EGraph::init();
+    for name in names {
+        graph.add_expression(Expression::multiply(
+            Expression::variable(name),
+            Expression::constant(1),
+        ));
+    }
+    graph
+}
+
+#[test]
+fn rules_over_the_limit_are_banned() {
+    let rule = identity();
+    let graph = products(&["x", "y", "z"]);
+    let mut scheduler = BackoffScheduler::new(1, 2);
+
+    let searched: Vec<usize> = (0..8)
+        .map(|iteration| scheduler.search_rule(iteration, &graph, &rule).len())
+        .collect();
+
+    // Banned for two iterations at first, then for four with a doubled
+    // threshold of two matches, after which the three matches get through.
+    assert_eq!(searched, vec![0, 0, 0, 0, 0, 0, 3, 3]);
+}
+
+#[test]
+fn runner_does_not_stop_while_rules_are_banned() {
+    let rule = identity();
+    let mut graph = products(&["x", "y"]);
+    let x = graph.add_expression(Expression::variable("x"));
+
+    let mut runner = Runner::new(graph, Limits::default());
+    runner.set_scheduler(BackoffScheduler::new(1, 100));
+    assert_eq!(runner.run(&[rule]).unwrap(), StopReason::Saturated);
+
+    let applied: Vec<usize> = runner
+        .iterations
+        .iter()
+        .map(|iteration| iteration.applied["Identity element of multiplication"])
+        .collect();
+    assert_eq!(applied, vec![0, 2, 2]);
+
+    let product = runner.egraph.add_expression(Expression::multiply(
+        Expression::variable("x"),
+        Expression::constant(1),
+    ));
+    assert_eq!(runner.egraph.find(product), runner.egraph.find(x));
+}
@@ -0,0 +1,47 @@
+use egraphs::graphs::*;
+use egraphs::rules::*;
+use egraphs::runner::*;
+use egraphs::symbol::Symbol;
+
+fn normalize(matches: Vec<(Subst, Id)>) -> Vec<(Vec<(Symbol, Id)>, Id)> {
+    let mut matches: Vec<_> = matches
+        .into_iter()
+        .map(|(subst, id)| {
+            let mut bindings: Vec<_> = subst.into_iter().collect();
+            bindings.sort();
+            (bindings, id)
+        })
+        .collect();
+    matches.sort();
+    matches
+}
+
+#[test]
+fn machine_agrees_with_interpreter() {
+    let mut graph: EGraph = EGraph::init();
+    graph.add_expression(Expression::divide(
+        Expression::multiply(Expression::variable("x"), Expression::constant(-6)),
+        Expression::multiply(Expression::variable("x"), Expression::constant(-3)),
+    ));
+    graph.add_expression(Expression::add(
+        Expression::multiply(
+            Expression::variable("y"),
+            Expression::add(Expression::variable("x"), Expression::constant(1)),
+        ),
+        Expression::negate(Expression::variable("y")),
+    ));
+
+    let limits = Limits {
+        iterations: 3,
+        ..Limits::default()
+    };
+    let mut runner = Runner::new(graph, limits);
+    runner.add_hook(|graph| {
+        for rule in Rule::rules() {
+            let machine = normalize(rule.search(graph));
+            assert_eq!(machine, normalize(graph.search_recursive(&rule.lhs)));
+        }
+        Ok(())
+    });
+    runner.run(&Rule::rules()).unwrap();
+}
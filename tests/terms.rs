@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+
+use egraphs::analysis::ConstantFolding;
+use egraphs::graphs::*;
+use egraphs::rules::*;
+use egraphs::runner::*;
+use egraphs::terms::TermCount;
+
+fn size(expression: &Expression) -> usize {
+    1 + expression.children.iter().map(size).sum::<usize>()
+}
+
+fn saturated() -> (EGraph<NodeType, ConstantFolding>, Id) {
+    let mut graph = EGraph::init();
+    let id = graph.add_expression(Expression::divide(
+        Expression::multiply(Expression::variable("x"), Expression::constant(-6)),
+        Expression::multiply(Expression::variable("x"), Expression::constant(-3)),
+    ));
+    let limits = Limits {
+        iterations: 3,
+        ..Limits::default()
+    };
+    let mut runner = Runner::new(graph, limits);
+    runner.run(&Rule::rules()).unwrap();
+    (runner.egraph, id)
+}
+
+#[test]
+fn terms_are_distinct_and_smallest_first() {
+    let (graph, id) = saturated();
+    let terms: Vec<Expression> = graph.extract_all(id).unwrap().take(500).collect();
+    assert!(terms.contains(&Expression::divide(
+        Expression::multiply(Expression::variable("x"), Expression::constant(-6)),
+        Expression::multiply(Expression::variable("x"), Expression::constant(-3)),
+    )));
+    assert!(terms
+        .windows(2)
+        .all(|pair| size(&pair[0]) <= size(&pair[1])));
+    assert_eq!(terms.iter().collect::<HashSet<_>>().len(), terms.len());
+}
+
+#[test]
+fn finite_classes_end() {
+    let mut graph: EGraph = EGraph::init();
+    let sum = graph.add_expression(Expression::add(
+        Expression::variable("x"),
+        Expression::variable("y"),
+    ));
+    let swapped = graph.add_expression(Expression::add(
+        Expression::variable("y"),
+        Expression::variable("x"),
+    ));
+    graph.union(sum, swapped).unwrap();
+    graph.rebuild();
+
+    let terms: Vec<String> = graph
+        .extract_all(sum)
+        .unwrap()
+        .map(|term| term.to_string())
+        .collect();
+    assert_eq!(terms, vec!["(x + y)", "(y + x)"]);
+    assert_eq!(graph.count_terms(sum, None).unwrap(), TermCount::Finite(2));
+}
+
+#[test]
+fn cyclic_classes_have_infinitely_many_terms() {
+    let mut graph: EGraph = EGraph::init();
+    let x = graph.add_expression(Expression::variable("x"));
+    let negated = graph.add_expression(Expression::negate(Expression::negate(
+        Expression::variable("x"),
+    )));
+    graph.union(x, negated).unwrap();
+    graph.rebuild();
+
+    let terms: Vec<String> = graph
+        .extract_all(x)
+        .unwrap()
+        .take(3)
+        .map(|term| term.to_string())
+        .collect();
+    assert_eq!(terms, vec!["x", "-(-(x))", "-(-(-(-(x))))"]);
+    assert_eq!(graph.count_terms(x, None).unwrap(), TermCount::Infinite);
+    assert_eq!(graph.count_terms(x, Some(5)).unwrap(), TermCount::Finite(3));
+}
+
+#[test]
+fn count_terms_agrees_with_extract_all() {
+    let (graph, id) = saturated();
+    let sizes: Vec<usize> = graph
+        .extract_all(id)
+        .unwrap()
+        .take_while(|term| size(term) <= 7)
+        .map(|term| size(&term))
+        .collect();
+    for max_size in 1..=7 {
+        let expected = sizes.iter().filter(|size| **size <= max_size).count();
+        assert_eq!(
+            graph.count_terms(id, Some(max_size)).unwrap(),
+            TermCount::Finite(expected as u128)
+        );
+    }
+}
+
+#[test]
+fn pending_unions_are_refused() {
+    let (mut graph, id) = saturated();
+    let y = graph.add_expression(Expression::variable("y"));
+    graph.union(y, id).unwrap();
+    assert_eq!(graph.count_terms(id, Some(3)), Err(EGraphError::NotRebuilt));
+    assert!(graph.sample_terms(id, 3, 0).is_err());
+    graph.rebuild();
+    assert!(graph.count_terms(id, Some(3)).is_ok());
+}
+
+#[test]
+fn samples_have_the_given_size() {
+    let (graph, id) = saturated();
+    let samples: Vec<Expression> = graph.sample_terms(id, 7, 0).unwrap().take(200).collect();
+    assert_eq!(samples.len(), 200);
+    assert!(samples.iter().all(|sample| size(sample) == 7));
+
+    let distinct: HashSet<&Expression> = samples.iter().collect();
+    let count = graph.count_terms(id, Some(7)).unwrap();
+    let smaller = graph.count_terms(id, Some(6)).unwrap();
+    let (TermCount::Finite(count), TermCount::Finite(smaller)) = (count, smaller) else {
+        panic!("the counts are finite");
+    };
+    assert!(distinct.len() as u128 <= count - smaller);
+    assert_eq!(
+        graph
+            .sample_terms(id, 7, 0)
+            .unwrap()
+            .take(200)
+            .collect::<Vec<_>>(),
+        samples
+    );
+}